@@ -6,7 +6,26 @@ fn is_required(attrs: &[syn::Attribute]) -> bool {
     attrs.iter().any(|attr| attr.path().is_ident("required"))
 }
 
+/// Whether the builder should derive `Deserialize`, requested by `#[new(deserialize)]`.
+fn is_deserialize(attrs: &[syn::Attribute]) -> bool {
+    let mut deserialize = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("new")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("deserialize") {
+                deserialize = true;
+            }
+            Ok(())
+        });
+    }
+    deserialize
+}
+
 fn get_rename(attrs: &[syn::Attribute]) -> Option<proc_macro2::TokenStream> {
+    let lit = get_rename_lit(attrs)?;
+    Some(quote! { #[serde(rename = #lit)] })
+}
+
+fn get_rename_lit(attrs: &[syn::Attribute]) -> Option<syn::LitStr> {
     for attr in attrs {
         // Only interested in attributes that look like #[serde(...)]
         if attr.path().is_ident("serde")
@@ -25,7 +44,7 @@ fn get_rename(attrs: &[syn::Attribute]) -> Option<proc_macro2::TokenStream> {
 
                 // Parse that string back into a syn::Lit (which handles quotes properly)
                 if let Ok(lit) = syn::parse_str::<syn::LitStr>(&value) {
-                    return Some(quote! { #[serde(rename = #lit)] });
+                    return Some(lit);
                 }
             }
         }
@@ -60,7 +79,7 @@ fn get_rename_all(attrs: &[syn::Attribute]) -> Option<proc_macro2::TokenStream>
     None
 }
 
-#[proc_macro_derive(New, attributes(required))]
+#[proc_macro_derive(New, attributes(required, new))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let input = syn::parse_macro_input!(input as DeriveInput);
     let ident = &input.ident;
@@ -156,9 +175,12 @@ pub fn derive(input: TokenStream) -> TokenStream {
     });
 
     let rename_all = get_rename_all(&input.attrs);
+    let deserialize =
+        is_deserialize(&input.attrs).then(|| quote! { #[derive(serde::Deserialize)] });
 
     let expanded = quote! {
         #[derive(Clone, Debug, PartialEq, serde::Serialize)]
+        #deserialize
         #rename_all
         pub struct #builder_ident {
             #(#options),*
@@ -187,6 +209,151 @@ pub fn derive(input: TokenStream) -> TokenStream {
     expanded.into()
 }
 
+/// Derives a `<Struct>Patch` struct, in which every field is optional and
+/// only serialized if it is set, including the fields marked as
+/// `#[required]` by [`New`](derive@New). Unlike the `New<Struct>` builder,
+/// it can describe a change which leaves the required fields untouched.
+#[proc_macro_derive(Patch)]
+pub fn derive_patch(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let patch_ident = syn::Ident::new(&format!("{ident}Patch"), ident.span());
+
+    let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
+        ..
+    }) = input.data
+    else {
+        return syn::Error::new_spanned(
+            ident,
+            "Patch can only be derived for structs with named fields",
+        )
+        .to_compile_error()
+        .into();
+    };
+
+    let options = named.iter().map(|field| {
+        let name = &field.ident;
+        let ty = &field.ty;
+        let rename = get_rename(&field.attrs);
+        quote! {
+            #rename
+            #[serde(skip_serializing_if = "Option::is_none")]
+            #name: std::option::Option<#ty>
+        }
+    });
+
+    let funcs = named.iter().filter_map(|field| {
+        let name = field.ident.as_ref()?;
+        let getter = proc_macro2::Ident::new(&format!("get_{name}"), name.span());
+        let ty = &field.ty;
+        Some(quote! {
+            pub fn #name(mut self, #name: #ty) -> Self {
+                self.#name = std::option::Option::Some(#name);
+                self
+            }
+
+            pub fn #getter(&self) -> &std::option::Option<#ty> {
+                &self.#name
+            }
+        })
+    });
+
+    let rename_all = get_rename_all(&input.attrs);
+    let doc = format!("Changes to a [`{ident}`], only the fields which are set are sent.");
+
+    let expanded = quote! {
+        #[doc = #doc]
+        #[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+        #rename_all
+        pub struct #patch_ident {
+            #(#options),*
+        }
+
+        impl #patch_ident {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            #(#funcs)*
+        }
+    };
+
+    expanded.into()
+}
+
+/// Derives a fieldless `<Enum>Kind` enum with the same variants, and a
+/// `kind()` method returning the kind of a value. The name of a kind is the
+/// serialized name of its variant.
+#[proc_macro_derive(Kind)]
+pub fn derive_kind(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let kind_ident = syn::Ident::new(&format!("{ident}Kind"), ident.span());
+
+    let syn::Data::Enum(syn::DataEnum { ref variants, .. }) = input.data else {
+        return syn::Error::new_spanned(ident, "Kind can only be derived for enums")
+            .to_compile_error()
+            .into();
+    };
+
+    let names = variants.iter().map(|variant| &variant.ident);
+    let all = names.clone();
+
+    let strs = variants.iter().map(|variant| {
+        let name = &variant.ident;
+        let value = get_rename_lit(&variant.attrs)
+            .unwrap_or_else(|| syn::LitStr::new(&name.to_string(), name.span()));
+        quote! { Self::#name => #value }
+    });
+
+    let kinds = variants.iter().map(|variant| {
+        let name = &variant.ident;
+        let pattern = match variant.fields {
+            syn::Fields::Named(_) => quote! { #name { .. } },
+            syn::Fields::Unnamed(_) => quote! { #name(..) },
+            syn::Fields::Unit => quote! { #name },
+        };
+        quote! { Self::#pattern => #kind_ident::#name }
+    });
+
+    let expanded = quote! {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub enum #kind_ident {
+            #(#names),*
+        }
+
+        impl #kind_ident {
+            /// All kinds, in the order of their declaration.
+            pub const ALL: &'static [#kind_ident] = &[#(#kind_ident::#all),*];
+
+            /// Returns the serialized name of the variant.
+            pub const fn as_str(&self) -> &'static str {
+                match self {
+                    #(#strs),*
+                }
+            }
+        }
+
+        impl std::fmt::Display for #kind_ident {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl #ident {
+            /// Returns the kind of this value.
+            pub const fn kind(&self) -> #kind_ident {
+                match self {
+                    #(#kinds),*
+                }
+            }
+        }
+    };
+
+    expanded.into()
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
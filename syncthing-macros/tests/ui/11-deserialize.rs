@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use serde_json::from_str;
+use syncthing_macros::New;
+
+// Deserializing the builder has to be requested explicitly
+#[derive(New, Serialize, Deserialize)]
+#[new(deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    #[required]
+    #[serde(rename = "deviceID")]
+    pub device_id: String,
+    pub foo_bar: u32,
+    pub opt: Option<()>,
+}
+
+fn main() {
+    let device: NewDevice = from_str(r#"{"deviceID": "foo", "fooBar": 12}"#).unwrap();
+
+    assert_eq!(device.get_device_id(), "foo");
+    assert_eq!(device.get_foo_bar(), &Some(12));
+    assert!(device.get_opt().is_none(), "Field 'opt' should be unset");
+
+    assert!(
+        from_str::<NewDevice>(r#"{"fooBar": 12}"#).is_err(),
+        "Field 'deviceID' should be required"
+    );
+}
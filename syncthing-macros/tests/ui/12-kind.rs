@@ -0,0 +1,28 @@
+use serde::Serialize;
+use syncthing_macros::Kind;
+
+#[derive(Kind, Serialize)]
+pub enum Event {
+    Started,
+    #[serde(rename = "stopped")]
+    Stopped(u32),
+    Changed {
+        from: String,
+        to: String,
+    },
+}
+
+fn main() {
+    let event = Event::Changed {
+        from: "a".to_string(),
+        to: "b".to_string(),
+    };
+    assert_eq!(event.kind(), EventKind::Changed);
+    assert_eq!(Event::Started.kind(), EventKind::Started);
+    assert_eq!(Event::Stopped(1).kind().as_str(), "stopped");
+    assert_eq!(EventKind::Changed.to_string(), "Changed");
+    assert_eq!(
+        EventKind::ALL,
+        &[EventKind::Started, EventKind::Stopped, EventKind::Changed]
+    );
+}
@@ -0,0 +1,27 @@
+use serde::Serialize;
+use serde_json::{from_value, json, to_value};
+use syncthing_macros::{New, Patch};
+
+#[derive(New, Patch, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Folder {
+    #[required]
+    pub id: String,
+    #[required]
+    pub folder_path: String,
+    #[serde(rename = "paused")]
+    pub is_paused: bool,
+}
+
+fn main() {
+    // Required fields of `New` are optional as well
+    let patch = FolderPatch::new().is_paused(true);
+    assert_eq!(to_value(&patch).unwrap(), json!({"paused": true}));
+    assert_eq!(patch.get_id(), &None);
+
+    let patch = FolderPatch::new().folder_path("/tmp".to_string());
+    assert_eq!(to_value(&patch).unwrap(), json!({"folderPath": "/tmp"}));
+
+    let patch: FolderPatch = from_value(json!({"paused": false})).unwrap();
+    assert_eq!(patch, FolderPatch::new().is_paused(false));
+}
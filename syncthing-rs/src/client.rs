@@ -1,26 +1,201 @@
 use crate::{
+    config_xml,
     error::{Error, Result},
+    event_stream::{DiskEventStream, EventQuery, EventStream, event_id},
+    http::{HttpClient, PasswordAuth},
     types::{
         cluster::{PendingDevices, PendingFolders},
         config::{
-            Configuration, DeviceConfiguration, FolderConfiguration, NewDeviceConfiguration,
-            NewFolderConfiguration,
+            ConfigRevision, Configuration, DefaultDeviceConfiguration, DeviceConfiguration,
+            DeviceConfigurationPatch, FolderConfiguration, FolderConfigurationPatch, FolderType,
+            NewDeviceConfiguration, NewFolderConfiguration, NewGuiConfiguration,
+            NewLDAPConfiguration, NewOptionsConfiguration, OptionsConfiguration, RestartRequired,
         },
-        db::Completion,
+        db::{
+            BrowseEntry, Completion, FileDetails, FilePage, FolderIgnores, FolderSummary, Need,
+            Paginated, Pagination,
+        },
+        device_id::DeviceId,
         events::Event,
-        system::Connections,
+        system::{
+            Connections, SyncthingVersion, SystemPaths, SystemStatus, SystemVersion, UpgradeInfo,
+        },
     },
 };
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use futures::{Stream, StreamExt};
 use reqwest::{StatusCode, header};
 use tokio::sync::broadcast::Sender;
 
 const ADDR: &str = "http://localhost:8384/rest";
 
+/// Interval in which the API is polled while waiting for a restart.
+const RESTART_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// First version providing the `/config` endpoints.
+const CONFIG_API_VERSION: SyncthingVersion = SyncthingVersion::new(1, 12, 0);
+
+/// First version providing the `/cluster/pending` endpoints.
+const PENDING_API_VERSION: SyncthingVersion = SyncthingVersion::new(1, 13, 0);
+
+/// How often a conflicting configuration modification is retried.
+const CONFIG_MODIFY_RETRIES: usize = 5;
+
+/// Keys identifying the elements of arrays in the configuration.
+const CONFIG_ID_KEYS: [&str; 2] = ["id", "deviceID"];
+
+/// Keys of map-valued fields in the configuration, whose entries are not
+/// fields of a struct.
+const CONFIG_MAP_KEYS: [&str; 1] = ["params"];
+
+/// Returns `edited` with all fields of `original`, which it does not contain.
+/// Array elements are matched by their ID if they have one, otherwise by
+/// their position. Maps are taken from `edited` as a whole, so that removed
+/// entries stay removed.
+fn merge_json(original: &serde_json::Value, edited: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+
+    match (original, edited) {
+        (Value::Object(original), Value::Object(edited)) => {
+            let mut merged = original.clone();
+            for (key, value) in edited {
+                let value = if CONFIG_MAP_KEYS.contains(&key.as_str()) {
+                    value
+                } else {
+                    merge_json(original.get(&key).unwrap_or(&Value::Null), value)
+                };
+                merged.insert(key, value);
+            }
+            Value::Object(merged)
+        }
+        (Value::Array(original), Value::Array(edited)) => {
+            let id = |value: &Value| {
+                CONFIG_ID_KEYS
+                    .iter()
+                    .find_map(|key| value.get(key).and_then(Value::as_str).map(str::to_string))
+            };
+            edited
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let matching = match id(&value) {
+                        Some(value_id) => original.iter().find(|o| id(o) == Some(value_id.clone())),
+                        None => original.get(i),
+                    };
+                    merge_json(matching.unwrap_or(&Value::Null), value)
+                })
+                .collect()
+        }
+        (_, edited) => edited,
+    }
+}
+
+/// Percent-encodes `value`, so that it can be used as a query parameter.
+fn encode_query(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for b in value.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~' | b'/') {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{b:02X}"));
+        }
+    }
+    encoded
+}
+
+/// Returns the REST API URL for a GUI listening on `address`, which may
+/// also contain a scheme, e.g. when set via `$STGUIADDRESS`.
+fn gui_base_url(address: &str, use_tls: bool) -> Result<String> {
+    let unsupported = || Error::UnsupportedGuiAddressError(address.to_string());
+    let (scheme, host_port) = match address.split_once("://") {
+        Some((scheme @ ("http" | "https" | "unix" | "unixs"), rest)) => (scheme, rest),
+        Some(_) => return Err(unsupported()),
+        // A plain path is a unix socket
+        None if address.starts_with('/') && use_tls => ("unixs", address),
+        None if address.starts_with('/') => ("unix", address),
+        None if use_tls => ("https", address),
+        None => ("http", address),
+    };
+    if scheme.starts_with("unix") {
+        return Ok(format!("{scheme}://{host_port}"));
+    }
+    let (host, port) = host_port.rsplit_once(':').ok_or_else(unsupported)?;
+    // Listening on all interfaces, connect via loopback instead
+    let host = match host {
+        "" | "0.0.0.0" => "127.0.0.1",
+        "[::]" => "[::1]",
+        host => host,
+    };
+    Ok(format!("{scheme}://{host}:{port}/rest"))
+}
+
+#[cfg(unix)]
+fn with_unix_socket(
+    client: reqwest::ClientBuilder,
+    path: String,
+) -> Result<reqwest::ClientBuilder> {
+    Ok(client.unix_socket(std::path::PathBuf::from(path)))
+}
+
+#[cfg(not(unix))]
+fn with_unix_socket(_: reqwest::ClientBuilder, path: String) -> Result<reqwest::ClientBuilder> {
+    Err(Error::UnsupportedGuiAddressError(format!("unix://{path}")))
+}
+
+/// Walks every page of a paginated endpoint, starting at the first one,
+/// until a page is not completely filled.
+fn paginate<'a, T, F, Fut>(per_page: u64, mut fetch: F) -> impl Stream<Item = Result<T>> + 'a
+where
+    T: Paginated + 'a,
+    F: FnMut(Pagination) -> Fut + 'a,
+    Fut: Future<Output = Result<T>> + 'a,
+{
+    // Syncthing falls back to its default page size for 0, so the last page
+    // could not be detected by being partially filled
+    let per_page = per_page.max(1);
+    futures::stream::try_unfold(Some(1), move |page: Option<u64>| {
+        let request = page.map(|page| (page, fetch(Pagination::new(page, per_page))));
+        async move {
+            let Some((page, request)) = request else {
+                return Ok(None);
+            };
+            let result = request.await?;
+            let num_items = result.num_items() as u64;
+            if num_items == 0 {
+                return Ok(None);
+            }
+            let next = (num_items >= per_page).then_some(page + 1);
+            Ok(Some((result, next)))
+        }
+    })
+}
+
+/// How the `Client` authenticates.
+enum Credentials {
+    ApiKey(String),
+    Password {
+        user: String,
+        password: String,
+        mode: PasswordAuth,
+    },
+}
+
 /// A `ClientBuilder` can be used to create a `Client` with custom configuration.
 #[must_use]
 pub struct ClientBuilder {
     base_url: Option<String>,
-    api_key: String,
+    credentials: Credentials,
+    version: Option<SyncthingVersion>,
+    root_certificates: Vec<Vec<u8>>,
+    pinned_certificate: Option<String>,
+    accept_invalid_certs: bool,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
 }
 
 impl ClientBuilder {
@@ -32,35 +207,277 @@ impl ClientBuilder {
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             base_url: None,
-            api_key: api_key.into(),
+            credentials: Credentials::ApiKey(api_key.into()),
+            version: None,
+            root_certificates: Vec::new(),
+            pinned_certificate: None,
+            accept_invalid_certs: false,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+        }
+    }
+
+    /// Constructs a `ClientBuilder`, which authenticates with the username
+    /// and password of the GUI instead of an API key, e.g. if the GUI uses LDAP.
+    ///
+    /// The client logs in before the first request, like the GUI does, and
+    /// sends the session cookie and CSRF token with every request. Once the
+    /// session expires, it logs in again.
+    pub fn with_password(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::with_password_auth(user.into(), password.into(), PasswordAuth::Session)
+    }
+
+    /// Constructs a `ClientBuilder`, which sends the username and password
+    /// of the GUI with every request using HTTP basic authentication.
+    ///
+    /// Unlike [`with_password`](ClientBuilder::with_password) this does not
+    /// require a session, but Syncthing verifies the password on every request.
+    pub fn with_basic_auth(user: impl Into<String>, password: impl Into<String>) -> Self {
+        Self::with_password_auth(user.into(), password.into(), PasswordAuth::Basic)
+    }
+
+    fn with_password_auth(user: String, password: String, mode: PasswordAuth) -> Self {
+        let mut builder = Self::new(String::new());
+        builder.credentials = Credentials::Password {
+            user,
+            password,
+            mode,
+        };
+        builder
+    }
+
+    /// Constructs a `ClientBuilder` for the Syncthing instance configured in
+    /// the `config.xml` at `path`, using its GUI address and API key.
+    ///
+    /// If the GUI listens on all interfaces, the loopback address is used.
+    /// If the GUI uses TLS with Syncthing's self-signed certificate, pin it
+    /// with [`pin_certificate`](ClientBuilder::pin_certificate).
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the file cannot be read or parsed, the GUI
+    /// address is not supported or there is no API key configured.
+    pub fn from_config_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let gui = config_xml::gui_from_str(&std::fs::read_to_string(path)?)?;
+        Self::from_gui(&gui.address, gui.use_tls, gui.api_key)
+    }
+
+    /// Constructs a `ClientBuilder` for the local Syncthing instance, see
+    /// [`config_xml::locate`] for where its `config.xml` is searched and
+    /// [`from_config_file`](ClientBuilder::from_config_file) for how it is used.
+    ///
+    /// Like Syncthing, the `$STGUIADDRESS` and `$STGUIAPIKEY` environment
+    /// variables override the values of the configuration file.
+    ///
+    /// # Errors
+    ///
+    /// This method fails with [`ConfigNotFoundError`](Error::ConfigNotFoundError)
+    /// if there is no configuration, or if it cannot be used, see
+    /// [`from_config_file`](ClientBuilder::from_config_file).
+    pub fn discover() -> Result<Self> {
+        let path = config_xml::locate().ok_or(Error::ConfigNotFoundError)?;
+        let mut gui = config_xml::gui_from_str(&std::fs::read_to_string(path)?)?;
+        let env = |name| std::env::var(name).ok().filter(|v| !v.is_empty());
+        if let Some(address) = env("STGUIADDRESS") {
+            gui.address = address;
+        }
+        if let Some(api_key) = env("STGUIAPIKEY") {
+            gui.api_key = api_key;
+        }
+        Self::from_gui(&gui.address, gui.use_tls, gui.api_key)
+    }
+
+    fn from_gui(address: &str, use_tls: bool, api_key: String) -> Result<Self> {
+        if api_key.is_empty() {
+            return Err(Error::InvalidConfigXmlError(
+                "no GUI API key configured".to_string(),
+            ));
         }
+        Ok(Self::new(api_key).base_url(gui_base_url(address, use_tls)?))
     }
 
     /// Set the syncthing URL to something different than `http://localhost:8384/rest`.
+    ///
+    /// To connect to a GUI listening on a unix socket, use `unix://` followed
+    /// by the path of the socket, e.g. `unix:///run/syncthing/gui.sock`, or
+    /// `unixs://` if the GUI uses TLS.
     pub fn base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = Some(url.into());
         self
     }
 
+    /// Trust the PEM encoded certificate(s) in `pem` in addition to the
+    /// system's root certificates, e.g. the CA which signed the GUI certificate.
+    pub fn add_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Only accept the GUI certificate with the given SHA-256 fingerprint,
+    /// in hexadecimal and optionally separated by colons, as printed by
+    /// `openssl x509 -noout -fingerprint -sha256 -in https-cert.pem`.
+    ///
+    /// This is the recommended way to connect to a GUI using Syncthing's
+    /// self-signed certificate. Neither the issuer, the host name nor the
+    /// expiry are checked, so [`build`](ClientBuilder::build) fails if
+    /// [`add_root_certificate`](ClientBuilder::add_root_certificate) or
+    /// [`danger_accept_invalid_certs`](ClientBuilder::danger_accept_invalid_certs)
+    /// are used as well.
+    pub fn pin_certificate(mut self, fingerprint: impl Into<String>) -> Self {
+        self.pinned_certificate = Some(fingerprint.into());
+        self
+    }
+
+    /// Accept any certificate, including invalid and self-signed ones.
+    ///
+    /// # Warning
+    ///
+    /// This makes the connection vulnerable to man-in-the-middle attacks,
+    /// which would expose the API key. Prefer
+    /// [`pin_certificate`](ClientBuilder::pin_certificate).
+    pub fn danger_accept_invalid_certs(mut self, accept_invalid_certs: bool) -> Self {
+        self.accept_invalid_certs = accept_invalid_certs;
+        self
+    }
+
+    /// Set a timeout for every request, from connecting until the response
+    /// body has been read.
+    ///
+    /// This also applies to requests which block until something happens,
+    /// such as polling for events, so it should be longer than their timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for establishing a connection.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send all requests via the proxy at `url`, e.g. `socks5://localhost:1080`.
+    ///
+    /// Without a proxy, the system's proxy configuration is used. It is
+    /// ignored when connecting to a unix socket.
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// Set the version of the Syncthing instance, instead of fetching it.
+    ///
+    /// If the version is known, methods which require a newer version return an
+    /// [`UnsupportedVersionError`](crate::error::Error::UnsupportedVersionError)
+    /// without sending a request.
+    pub fn version(mut self, version: SyncthingVersion) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Returns a `Client` that uses this `ClientBuilder` configuration, and
+    /// fetches and caches the version of the Syncthing instance, unless it
+    /// was already set with [`version`](ClientBuilder::version).
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the client cannot be built, the API cannot
+    /// be reached or the version cannot be parsed.
+    pub async fn build_with_version(self) -> Result<Client> {
+        let client = self.build()?;
+        client.syncthing_version().await?;
+        Ok(client)
+    }
+
     /// Returns a `Client` that uses this `ClientBuilder` configuration.
     ///
     /// # Errors
     ///
-    /// This method fails if the header cannot be created or the HTTP client
-    /// cannot be initialized.
+    /// This method fails if the header cannot be created, a certificate or
+    /// the proxy URL is invalid, a pinned certificate is combined with other
+    /// TLS options, or the HTTP client cannot be initialized.
     pub fn build(self) -> Result<Client> {
-        let base_url = self.base_url.unwrap_or_else(|| ADDR.to_string());
+        if self.pinned_certificate.is_some() {
+            if !self.root_certificates.is_empty() {
+                return Err(Error::ConflictingTlsOptionsError(
+                    "root certificates".to_string(),
+                ));
+            }
+            if self.accept_invalid_certs {
+                return Err(Error::ConflictingTlsOptionsError(
+                    "accepting invalid certificates".to_string(),
+                ));
+            }
+        }
+        let mut base_url = self.base_url.unwrap_or_else(|| ADDR.to_string());
+        let unix_socket = ["unix", "unixs"].into_iter().find_map(|scheme| {
+            let path = base_url.strip_prefix(scheme)?.strip_prefix("://")?;
+            Some((scheme, path.to_string()))
+        });
 
         let mut headers = header::HeaderMap::new();
-        let mut api_key_header = header::HeaderValue::from_str(&self.api_key)?;
-        api_key_header.set_sensitive(true);
-        headers.insert("X-API-KEY", api_key_header);
+        if let Credentials::ApiKey(api_key) = &self.credentials {
+            let mut api_key_header = header::HeaderValue::from_str(api_key)?;
+            api_key_header.set_sensitive(true);
+            headers.insert("X-API-KEY", api_key_header);
+        }
 
-        let client = reqwest::Client::builder()
+        let mut client = reqwest::Client::builder()
             .default_headers(headers)
-            .build()?;
+            .tls_danger_accept_invalid_certs(self.accept_invalid_certs);
+        for pem in &self.root_certificates {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem)
+                .map_err(|e| Error::InvalidCertificateError(e.to_string()))?;
+            if certificates.is_empty() {
+                return Err(Error::InvalidCertificateError(
+                    "no PEM certificate found".to_string(),
+                ));
+            }
+            client = client.tls_certs_merge(certificates);
+        }
+        if let Some(fingerprint) = &self.pinned_certificate {
+            let fingerprint = crate::tls::parse_fingerprint(fingerprint)?;
+            client = client.tls_backend_preconfigured(crate::tls::pinned_config(fingerprint)?);
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            client = client.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        if let Some((scheme, path)) = unix_socket {
+            // The host is ignored, all connections use the socket
+            base_url = if scheme == "unixs" {
+                "https://localhost/rest"
+            } else {
+                "http://localhost/rest"
+            }
+            .to_string();
+            client = with_unix_socket(client, path)?;
+        }
+        let client = match self.credentials {
+            Credentials::ApiKey(_) => HttpClient::new(client.build()?),
+            Credentials::Password {
+                user,
+                password,
+                mode,
+            } => HttpClient::with_password(client.build()?, &base_url, user, password, mode),
+        };
 
-        Ok(Client { client, base_url })
+        let version = Arc::new(OnceLock::new());
+        if let Some(v) = self.version {
+            let _ = version.set(v);
+        }
+
+        Ok(Client {
+            client,
+            base_url,
+            version,
+        })
     }
 }
 
@@ -71,8 +488,9 @@ impl ClientBuilder {
 /// use `Client::builder()`.
 #[derive(Clone, Debug)]
 pub struct Client {
-    client: reqwest::Client,
+    client: HttpClient,
     base_url: String,
+    version: Arc<OnceLock<SyncthingVersion>>,
 }
 
 impl Client {
@@ -89,6 +507,19 @@ impl Client {
         ClientBuilder::new(api_key).build().expect("Client::new()")
     }
 
+    /// Creates a client for the local Syncthing instance, using the
+    /// address and API key from its configuration file.
+    ///
+    /// This is the same as `ClientBuilder::discover()?.build()`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the configuration cannot be found or used, see
+    /// [`ClientBuilder::discover`], or the client cannot be initialized.
+    pub fn discover() -> Result<Self> {
+        ClientBuilder::discover()?.build()
+    }
+
     /// Creates a `ClientBuilder` to configure a `Client`.
     /// This is the same as `ClientBuilder::new()`
     ///
@@ -98,6 +529,33 @@ impl Client {
         ClientBuilder::new(api_key)
     }
 
+    /// Returns the parsed version of the Syncthing instance. The version is
+    /// fetched once and cached afterwards.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the API cannot be reached or the version
+    /// cannot be parsed.
+    pub async fn syncthing_version(&self) -> Result<SyncthingVersion> {
+        if let Some(version) = self.version.get() {
+            return Ok(version.clone());
+        }
+        let version = self.get_version().await?.parse()?;
+        Ok(self.version.get_or_init(|| version).clone())
+    }
+
+    /// Fails with an [`UnsupportedVersionError`](crate::error::Error::UnsupportedVersionError)
+    /// if the version is known and older than `required`.
+    fn require_version(&self, required: &SyncthingVersion) -> Result<()> {
+        match self.version.get() {
+            Some(running) if running < required => Err(Error::UnsupportedVersionError {
+                required: Box::new(required.clone()),
+                running: Box::new(running.clone()),
+            }),
+            _ => Ok(()),
+        }
+    }
+
     /// Gets all the connections
     pub async fn get_connections(&self) -> Result<Connections> {
         log::debug!("GET /system/connections");
@@ -111,6 +569,47 @@ impl Client {
             .await?)
     }
 
+    /// Returns information about the running instance, such as its uptime,
+    /// memory usage and the status of listeners and discovery.
+    pub async fn get_system_status(&self) -> Result<SystemStatus> {
+        log::debug!("GET /system/status");
+        Ok(self
+            .client
+            .get(format!("{}/system/status", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the version and build information of the running instance.
+    pub async fn get_version(&self) -> Result<SystemVersion> {
+        log::debug!("GET /system/version");
+        Ok(self
+            .client
+            .get(format!("{}/system/version", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the paths used by the running instance, such as the
+    /// location of the configuration and the database.
+    pub async fn get_paths(&self) -> Result<SystemPaths> {
+        log::debug!("GET /system/paths");
+        Ok(self
+            .client
+            .get(format!("{}/system/paths", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
     /// Returns `()` if the syncthing API can be reached.
     ///
     /// Use [`health`](crate::client::Client::health) to do the same
@@ -141,82 +640,76 @@ impl Client {
         Ok(())
     }
 
-    /// Returns the ID of the current device. This endpoint
-    /// does not require a valid `api_key`.
-    pub async fn get_id(&self) -> Result<String> {
-        log::debug!("GET /noauth/health");
-        Ok(self
-            .client
-            .get(format!("{}/noauth/health", self.base_url))
+    /// Restarts Syncthing.
+    ///
+    /// Use [`restart_and_wait`](crate::client::Client::restart_and_wait) to wait
+    /// until the API is available again.
+    pub async fn restart(&self) -> Result<()> {
+        log::debug!("POST /system/restart");
+        self.client
+            .post(format!("{}/system/restart", self.base_url))
             .send()
             .await?
-            .error_for_status()?
-            .headers()
-            .get("X-Syncthing-Id")
-            .ok_or(Error::HeaderDeviceIDError)?
-            .to_str()
-            .map_err(|_| Error::HeaderParseError)?
-            .to_string())
+            .error_for_status()?;
+
+        Ok(())
     }
 
-    /// Only returns if an error is encountered.
-    /// Transmits every new [event](crate::types::events::Event) over `tx`.
-    /// If `skip_old`, all events before the call to this function do not
-    /// result in a transmission.
-    pub async fn get_events(&self, tx: Sender<Event>, mut skip_old: bool) -> Result<()> {
-        let mut current_id = 0;
-        loop {
-            log::debug!("GET /events");
-            let events: Vec<Event> = self
-                .client
-                .get(format!("{}/events?since={}", self.base_url, current_id))
-                .send()
-                .await?
-                .error_for_status()?
-                .json()
-                .await?;
-
-            log::debug!("received {} new events", events.len());
-            for event in events {
-                current_id = event.id;
-                if !skip_old {
-                    tx.send(event)?;
+    /// Restarts Syncthing and waits until the new instance answers.
+    ///
+    /// The unauthenticated [`health`](crate::client::Client::health) endpoint
+    /// is polled until it responds, then the new instance is detected by a
+    /// change of its [`start_time`](SystemStatus::start_time), which requires
+    /// valid credentials. Any error while waiting is retried, so this returns a
+    /// [`TimeoutError`](crate::error::Error::TimeoutError) if the new instance
+    /// is not observed within `timeout`.
+    pub async fn restart_and_wait(&self, timeout: Duration) -> Result<()> {
+        let before = self.start_time().await?;
+        self.restart().await?;
+        tokio::time::timeout(timeout, async {
+            loop {
+                tokio::time::sleep(RESTART_POLL_INTERVAL).await;
+                if let Err(e) = self.health().await {
+                    log::debug!("waiting for syncthing to come back: {e}");
+                    continue;
+                }
+                match self.start_time().await {
+                    Ok(start_time) if start_time != before => return,
+                    Ok(_) => {}
+                    Err(e) => log::debug!("waiting for syncthing to come back: {e}"),
                 }
             }
-            log::debug!("current event id is {current_id}");
-            skip_old = false;
-        }
+        })
+        .await
+        .map_err(|_| Error::TimeoutError)
     }
 
-    /// Returns the entire [`Configuration`]
-    ///
-    /// # Errors
-    ///
-    /// This method fails if the API cannot be reached, the server
-    /// answers with an error code or the JSON cannot be parsed.
-    pub async fn get_configuration(&self) -> Result<Configuration> {
-        log::debug!("GET /config");
-        Ok(self
+    /// Returns when Syncthing was started. Only this field of
+    /// [`SystemStatus`] is parsed, so it does not break if others change.
+    pub(crate) async fn start_time(&self) -> Result<chrono::DateTime<chrono::Utc>> {
+        #[derive(serde::Deserialize)]
+        struct StartTime {
+            #[serde(rename = "startTime")]
+            start_time: chrono::DateTime<chrono::Utc>,
+        }
+
+        log::debug!("GET /system/status (start time)");
+        let status: StartTime = self
             .client
-            .get(format!("{}/config", self.base_url))
+            .get(format!("{}/system/status", self.base_url))
             .send()
             .await?
             .error_for_status()?
             .json()
-            .await?)
+            .await?;
+        Ok(status.start_time)
     }
 
-    /// Posts a folder. If the folder already exists, it is replaced,
-    /// otherwise a new one is added.
-    ///
-    /// Use [`add_folder`](crate::client::Client::add_folder) if the operation
-    /// should fail if a folder with the same ID already exists.
-    pub async fn post_folder(&self, folder: impl Into<NewFolderConfiguration>) -> Result<()> {
-        let folder = folder.into();
-        log::debug!("POST /config/folders {folder:?}");
+    /// Shuts Syncthing down.
+    pub async fn shutdown(&self) -> Result<()> {
+        log::debug!("POST /system/shutdown");
         self.client
-            .post(format!("{}/config/folders", self.base_url))
-            .json(&folder)
+            .post(format!("{}/system/shutdown", self.base_url))
             .send()
             .await?
             .error_for_status()?;
@@ -224,63 +717,437 @@ impl Client {
         Ok(())
     }
 
-    /// Adds a new folder. If the folder already exists, a
-    /// [`DuplicateFolderError`](crate::error::Error::DuplicateFolderError) is returned.
-    /// This requires an additional check against the API.
-    ///
-    /// Use [`post_folder`](crate::client::Client::post_folder) if the operation
-    /// should blindly set the folder.
-    pub async fn add_folder(&self, folder: impl Into<NewFolderConfiguration>) -> Result<()> {
-        let folder = folder.into();
-        match self.get_folder(folder.get_id()).await {
-            Ok(_) => return Err(Error::DuplicateFolderError),
-            Err(Error::UnknownFolderError) => (),
-            Err(e) => return Err(e),
-        }
-        self.post_folder(folder).await
-    }
-
-    /// Gets the configuration for the folder with the ID `folder_id`. Explicitly
-    /// returns a [`UnknownFolderError`](crate::error::Error::UnknownFolderError)
-    /// if no folder with `folder_id` exists.
-    pub async fn get_folder(&self, folder_id: &str) -> Result<FolderConfiguration> {
-        log::debug!("GET /config/folders/{folder_id}");
-        let response = self
-            .client
-            .get(format!("{}/config/folders/{}", self.base_url, folder_id))
+    /// Erases the database index and restarts Syncthing. If `folder_id` is set,
+    /// only the index of that folder is erased.
+    pub async fn reset_database(&self, folder_id: Option<&str>) -> Result<()> {
+        let folder_str = match folder_id {
+            Some(folder_id) => format!("?folder={}", encode_query(folder_id)),
+            None => String::new(),
+        };
+        log::debug!("POST /system/reset{folder_str}");
+        self.client
+            .post(format!("{}/system/reset{}", self.base_url, folder_str))
             .send()
-            .await?;
+            .await?
+            .error_for_status()?;
 
-        if response.status() == StatusCode::NOT_FOUND {
-            // TODO check that really the folder ID is causing that
-            Err(Error::UnknownFolderError)
-        } else {
-            Ok(response.error_for_status()?.json().await?)
-        }
+        Ok(())
     }
 
-    /// Deletes the folder with the ID `folder_id`.
-    pub async fn delete_folder(&self, folder_id: &str) -> Result<()> {
-        log::debug!("DELETE /config/folders/{folder_id}");
-        self.client
-            .delete(format!("{}/config/folders/{}", self.base_url, folder_id))
+    /// Checks for a possible upgrade. Fails if the running build
+    /// does not support upgrades, e.g. in the official Docker image.
+    pub async fn get_upgrade(&self) -> Result<UpgradeInfo> {
+        log::debug!("GET /system/upgrade");
+        Ok(self
+            .client
+            .get(format!("{}/system/upgrade", self.base_url))
             .send()
             .await?
-            .error_for_status()?;
-        Ok(())
+            .error_for_status()?
+            .json()
+            .await?)
     }
 
-    /// Posts a device. If the device already exists, it is replaced,
-    /// otherwise a new one is added.
-    ///
-    /// Use [`add_device`](crate::client::Client::add_device) if the operation
-    /// should fail if a device with the same ID already exists.
-    pub async fn post_device(&self, device: impl Into<NewDeviceConfiguration>) -> Result<()> {
-        let device = device.into();
-        log::debug!("POST /config/devices {device:?}");
+    /// Performs an upgrade to the newest released version and restarts Syncthing.
+    /// Does nothing if there is no newer version.
+    pub async fn upgrade(&self) -> Result<()> {
+        log::debug!("POST /system/upgrade");
         self.client
-            .post(format!("{}/config/devices", self.base_url))
-            .json(&device)
+            .post(format!("{}/system/upgrade", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Returns the ID of the current device. This endpoint
+    /// does not require a valid `api_key`.
+    pub async fn get_id(&self) -> Result<DeviceId> {
+        log::debug!("GET /noauth/health");
+        self.client
+            .get(format!("{}/noauth/health", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .headers()
+            .get("X-Syncthing-Id")
+            .ok_or(Error::HeaderDeviceIDError)?
+            .to_str()
+            .map_err(|_| Error::HeaderParseError)?
+            .parse()
+    }
+
+    /// Only returns if an error is encountered, which is not transient.
+    /// Transmits every new [event](crate::types::events::Event) over `tx`.
+    /// If `skip_old`, all events before the call to this function do not
+    /// result in a transmission. Events which cannot be parsed are skipped.
+    ///
+    /// See [`events`](Client::events) for a [`Stream`] of events.
+    pub async fn get_events(&self, tx: Sender<Event>, skip_old: bool) -> Result<()> {
+        let since = if skip_old {
+            self.latest_event_id(&EventQuery::default()).await?
+        } else {
+            0
+        };
+        let mut events = self.events().since(since);
+        while let Some(event) = events.next().await {
+            match event {
+                Ok(event) => {
+                    tx.send(event)?;
+                }
+                Err(e @ Error::InvalidEventError { .. }) => log::warn!("skipping event: {e}"),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a [`Stream`] of all [events](crate::types::events::Event),
+    /// which survives restarts of Syncthing, see [`EventStream`].
+    ///
+    /// ```no_run
+    /// # async fn example(client: syncthing_rs::Client) -> syncthing_rs::error::Result<()> {
+    /// use futures::TryStreamExt;
+    ///
+    /// let mut events = client.events().since(42);
+    /// while let Some(event) = events.try_next().await? {
+    ///     println!("{event:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn events(&self) -> EventStream {
+        EventStream::new(self.clone(), false)
+    }
+
+    /// Returns a [`Stream`] of only
+    /// [`LocalChangeDetected`](crate::types::events::EventType::LocalChangeDetected)
+    /// and [`RemoteChangeDetected`](crate::types::events::EventType::RemoteChangeDetected)
+    /// events, see [`DiskEventStream`].
+    ///
+    /// These are buffered separately by Syncthing, so they are kept much
+    /// longer than with [`events`](Client::events), where they could be
+    /// displaced by frequent events such as download progress.
+    pub fn disk_events(&self) -> DiskEventStream {
+        DiskEventStream::new(self.clone())
+    }
+
+    /// Long polls for events matching `query`. Every event is parsed on its
+    /// own, so a single invalid event does not fail the whole request.
+    pub(crate) async fn fetch_events(&self, query: &EventQuery) -> Result<Vec<Result<Event>>> {
+        let path = if query.disk {
+            "/events/disk"
+        } else {
+            "/events"
+        };
+        let mut url = format!("{}{path}?since={}", self.base_url, query.since);
+        if let Some(limit) = query.limit {
+            url.push_str(&format!("&limit={limit}"));
+        }
+        if let Some(timeout) = query.timeout {
+            url.push_str(&format!("&timeout={}", timeout.as_secs()));
+        }
+        if let Some(events) = &query.events {
+            url.push_str(&format!("&events={}", encode_query(events)));
+        }
+
+        log::debug!("GET {path}");
+        let events: Vec<serde_json::Value> = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        log::debug!("received {} new events", events.len());
+        Ok(events
+            .into_iter()
+            .map(|event| {
+                let id = event.get("id").and_then(serde_json::Value::as_u64);
+                serde_json::from_value(event).map_err(|source| Error::InvalidEventError {
+                    id: id.unwrap_or_default(),
+                    source,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the ID of the latest event of the same subscription as
+    /// `query`, `0` if there is none.
+    pub(crate) async fn latest_event_id(&self, query: &EventQuery) -> Result<u64> {
+        let query = EventQuery {
+            disk: query.disk,
+            since: 0,
+            limit: Some(1),
+            // Return immediately instead of waiting for the first event
+            timeout: Some(Duration::ZERO),
+            events: query.events.clone(),
+        };
+        Ok(self
+            .fetch_events(&query)
+            .await?
+            .iter()
+            .filter_map(event_id)
+            .max()
+            .unwrap_or_default())
+    }
+
+    /// Returns the entire [`Configuration`]
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the API cannot be reached, the server
+    /// answers with an error code or the JSON cannot be parsed.
+    pub async fn get_configuration(&self) -> Result<Configuration> {
+        Ok(self.get_configuration_with_revision().await?.0)
+    }
+
+    /// Returns the entire [`Configuration`] together with its [`ConfigRevision`],
+    /// which can be passed to [`put_configuration`](crate::client::Client::put_configuration).
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the API cannot be reached, the server
+    /// answers with an error code or the JSON cannot be parsed.
+    pub async fn get_configuration_with_revision(&self) -> Result<(Configuration, ConfigRevision)> {
+        let (raw, revision) = self.get_raw_configuration().await?;
+        Ok((serde_json::from_value(raw)?, revision))
+    }
+
+    /// Returns the configuration as it was sent by Syncthing, including
+    /// fields which are not part of [`Configuration`].
+    async fn get_raw_configuration(&self) -> Result<(serde_json::Value, ConfigRevision)> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("GET /config");
+        let body = self
+            .client
+            .get(format!("{}/config", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        let raw: serde_json::Value = serde_json::from_slice(&body)?;
+        let version = raw["version"].as_u64().unwrap_or_default();
+        Ok((raw, ConfigRevision::new(version, &body)))
+    }
+
+    /// Replaces the entire configuration with `config`, but only if the
+    /// configuration has not changed since `revision` was read. Otherwise, a
+    /// [`ConfigurationConflictError`](crate::error::Error::ConfigurationConflictError)
+    /// is returned and nothing is written.
+    ///
+    /// Fields which are not part of [`Configuration`], e.g. because they were
+    /// added in a newer version of Syncthing, keep their current value.
+    /// Folders and devices are matched by their ID to preserve those fields.
+    ///
+    /// Syncthing itself has no support for conditional writes, so this requires
+    /// an additional read. A change between that read and the write can still
+    /// get lost, but the window is small.
+    ///
+    /// Use [`modify_configuration`](crate::client::Client::modify_configuration)
+    /// to automatically retry on conflicts.
+    pub async fn put_configuration(
+        &self,
+        config: &Configuration,
+        revision: &ConfigRevision,
+    ) -> Result<()> {
+        let (current, found) = self.get_raw_configuration().await?;
+        if found != *revision {
+            return Err(Error::ConfigurationConflictError {
+                expected: revision.clone(),
+                found,
+            });
+        }
+
+        let merged = merge_json(&current, serde_json::to_value(config)?);
+        log::debug!("PUT /config");
+        self.client
+            .put(format!("{}/config", self.base_url))
+            .json(&merged)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Reads the configuration, applies `modify` to it and writes it back using
+    /// [`put_configuration`](crate::client::Client::put_configuration). If the
+    /// configuration was changed concurrently, this is retried with the new
+    /// configuration up to five times. Nothing is written
+    /// if `modify` does not change the configuration.
+    ///
+    /// Returns the configuration as it was written.
+    ///
+    /// ```no_run
+    /// # async fn example(client: syncthing_rs::Client) -> syncthing_rs::error::Result<()> {
+    /// client
+    ///     .modify_configuration(|config| config.options.relays_enabled = false)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn modify_configuration(
+        &self,
+        mut modify: impl FnMut(&mut Configuration),
+    ) -> Result<Configuration> {
+        let mut retries = 0;
+        loop {
+            let (original, revision) = self.get_configuration_with_revision().await?;
+            let mut config = original.clone();
+            modify(&mut config);
+            if config == original {
+                return Ok(config);
+            }
+
+            match self.put_configuration(&config, &revision).await {
+                Err(Error::ConfigurationConflictError { expected, found })
+                    if retries < CONFIG_MODIFY_RETRIES =>
+                {
+                    retries += 1;
+                    log::debug!("configuration changed from {expected} to {found}, retrying");
+                }
+                result => return result.map(|()| config),
+            }
+        }
+    }
+
+    /// Returns whether the configuration has changes which only
+    /// take effect after a [`restart`](crate::client::Client::restart).
+    pub async fn is_restart_required(&self) -> Result<bool> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("GET /config/restart-required");
+        let response: RestartRequired = self
+            .client
+            .get(format!("{}/config/restart-required", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        Ok(response.requires_restart)
+    }
+
+    /// Posts a folder. If the folder already exists, it is replaced,
+    /// otherwise a new one is added.
+    ///
+    /// Use [`add_folder`](crate::client::Client::add_folder) if the operation
+    /// should fail if a folder with the same ID already exists.
+    pub async fn post_folder(&self, folder: impl Into<NewFolderConfiguration>) -> Result<()> {
+        let folder = folder.into();
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("POST /config/folders {folder:?}");
+        self.client
+            .post(format!("{}/config/folders", self.base_url))
+            .json(&folder)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Adds a new folder. If the folder already exists, a
+    /// [`DuplicateFolderError`](crate::error::Error::DuplicateFolderError) is returned.
+    /// This requires an additional check against the API.
+    ///
+    /// Use [`post_folder`](crate::client::Client::post_folder) if the operation
+    /// should blindly set the folder.
+    pub async fn add_folder(&self, folder: impl Into<NewFolderConfiguration>) -> Result<()> {
+        let folder = folder.into();
+        match self.get_folder(folder.get_id()).await {
+            Ok(_) => return Err(Error::DuplicateFolderError),
+            Err(Error::UnknownFolderError) => (),
+            Err(e) => return Err(e),
+        }
+        self.post_folder(folder).await
+    }
+
+    /// Gets the configuration for the folder with the ID `folder_id`. Explicitly
+    /// returns a [`UnknownFolderError`](crate::error::Error::UnknownFolderError)
+    /// if no folder with `folder_id` exists.
+    pub async fn get_folder(&self, folder_id: &str) -> Result<FolderConfiguration> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("GET /config/folders/{folder_id}");
+        let response = self
+            .client
+            .get(format!("{}/config/folders/{}", self.base_url, folder_id))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            // TODO check that really the folder ID is causing that
+            Err(Error::UnknownFolderError)
+        } else {
+            Ok(response.error_for_status()?.json().await?)
+        }
+    }
+
+    /// Deletes the folder with the ID `folder_id`.
+    pub async fn delete_folder(&self, folder_id: &str) -> Result<()> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("DELETE /config/folders/{folder_id}");
+        self.client
+            .delete(format!("{}/config/folders/{}", self.base_url, folder_id))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Changes only the fields of the folder with the ID `folder_id` which
+    /// are set in `folder`, all others keep their current value. Explicitly
+    /// returns a [`UnknownFolderError`](crate::error::Error::UnknownFolderError)
+    /// if no folder with `folder_id` exists, and a
+    /// [`MismatchedIdError`](crate::error::Error::MismatchedIdError) if
+    /// `folder` sets a different ID.
+    pub async fn patch_folder(
+        &self,
+        folder_id: &str,
+        folder: FolderConfigurationPatch,
+    ) -> Result<()> {
+        if let Some(id) = folder.get_id()
+            && id != folder_id
+        {
+            return Err(Error::MismatchedIdError {
+                expected: folder_id.to_string(),
+                found: id.clone(),
+            });
+        }
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PATCH /config/folders/{folder_id} {folder:?}");
+        let response = self
+            .client
+            .patch(format!("{}/config/folders/{}", self.base_url, folder_id))
+            .json(&folder)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            Err(Error::UnknownFolderError)
+        } else {
+            response.error_for_status()?;
+            Ok(())
+        }
+    }
+
+    /// Posts a device. If the device already exists, it is replaced,
+    /// otherwise a new one is added.
+    ///
+    /// Use [`add_device`](crate::client::Client::add_device) if the operation
+    /// should fail if a device with the same ID already exists.
+    pub async fn post_device(&self, device: impl Into<NewDeviceConfiguration>) -> Result<()> {
+        let device = device.into();
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("POST /config/devices {device:?}");
+        self.client
+            .post(format!("{}/config/devices", self.base_url))
+            .json(&device)
             .send()
             .await?
             .error_for_status()?;
@@ -305,7 +1172,8 @@ impl Client {
     }
 
     /// Gets the configuration for the device with the ID `device_id`.
-    pub async fn get_device(&self, device_id: &str) -> Result<DeviceConfiguration> {
+    pub async fn get_device(&self, device_id: &DeviceId) -> Result<DeviceConfiguration> {
+        self.require_version(&CONFIG_API_VERSION)?;
         log::debug!("GET /config/devices/{device_id}");
         let response = self
             .client
@@ -322,7 +1190,8 @@ impl Client {
     }
 
     /// Deletes the device with the ID `device_id`.
-    pub async fn delete_device(&self, device_id: &str) -> Result<()> {
+    pub async fn delete_device(&self, device_id: &DeviceId) -> Result<()> {
+        self.require_version(&CONFIG_API_VERSION)?;
         log::debug!("DELETE /config/devices/{device_id}");
         self.client
             .delete(format!("{}/config/devices/{}", self.base_url, device_id))
@@ -332,9 +1201,46 @@ impl Client {
         Ok(())
     }
 
+    /// Changes only the fields of the device with the ID `device_id` which
+    /// are set in `device`, all others keep their current value. Explicitly
+    /// returns a [`UnknownDeviceError`](crate::error::Error::UnknownDeviceError)
+    /// if no device with `device_id` exists, and a
+    /// [`MismatchedIdError`](crate::error::Error::MismatchedIdError) if
+    /// `device` sets a different ID.
+    pub async fn patch_device(
+        &self,
+        device_id: &DeviceId,
+        device: DeviceConfigurationPatch,
+    ) -> Result<()> {
+        if let Some(id) = device.get_device_id()
+            && id != device_id
+        {
+            return Err(Error::MismatchedIdError {
+                expected: device_id.to_string(),
+                found: id.to_string(),
+            });
+        }
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PATCH /config/devices/{device_id} {device:?}");
+        let response = self
+            .client
+            .patch(format!("{}/config/devices/{}", self.base_url, device_id))
+            .json(&device)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            Err(Error::UnknownDeviceError)
+        } else {
+            response.error_for_status()?;
+            Ok(())
+        }
+    }
+
     /// Gets a list of all pending remote devices which have tried to connect but
     /// are not in our configuration yet.
     pub async fn get_pending_devices(&self) -> Result<PendingDevices> {
+        self.require_version(&PENDING_API_VERSION)?;
         log::debug!("GET /cluster/pending/devices");
         Ok(self
             .client
@@ -349,6 +1255,7 @@ impl Client {
     /// Gets all folders which remote devices have offered to us, but are not yet shared
     /// from our instance to them or are not present on our instance.
     pub async fn get_pending_folders(&self) -> Result<PendingFolders> {
+        self.require_version(&PENDING_API_VERSION)?;
         log::debug!("GET /cluster/pending/folders");
         Ok(self
             .client
@@ -363,7 +1270,8 @@ impl Client {
     /// Remove record about pending remote device with ID `device_id` which tried to connect.
     ///
     /// This is not permanent, use `ignore_device` instead.
-    pub async fn dismiss_pending_device(&self, device_id: &str) -> Result<()> {
+    pub async fn dismiss_pending_device(&self, device_id: &DeviceId) -> Result<()> {
+        self.require_version(&PENDING_API_VERSION)?;
         log::debug!("DELETE /cluster/pending/devices?device={device_id}");
         self.client
             .delete(format!(
@@ -385,12 +1293,14 @@ impl Client {
     pub async fn dismiss_pending_folder(
         &self,
         folder_id: &str,
-        device_id: Option<&str>,
+        device_id: Option<&DeviceId>,
     ) -> Result<()> {
         let device_str = match device_id {
             Some(device_id) => format!("&device={device_id}"),
             None => String::new(),
         };
+        self.require_version(&PENDING_API_VERSION)?;
+        let folder_id = encode_query(folder_id);
         log::debug!("DELETE /cluster/pending/folders?folder={folder_id}{device_str}");
         self.client
             .delete(format!(
@@ -405,8 +1315,10 @@ impl Client {
     }
 
     /// Returns a template device configuration with all default values,
-    /// which only requires a unique device ID to be instantiated.
-    pub async fn get_default_device(&self) -> Result<DeviceConfiguration> {
+    /// which only requires a unique device ID to be
+    /// [instantiated](DefaultDeviceConfiguration::into_device).
+    pub async fn get_default_device(&self) -> Result<DefaultDeviceConfiguration> {
+        self.require_version(&CONFIG_API_VERSION)?;
         log::debug!("GET /config/defaults/device");
         Ok(self
             .client
@@ -421,6 +1333,7 @@ impl Client {
     /// Returns a template folder configuration with all default values,
     /// which only requires a unique folder ID to be instantiated.
     pub async fn get_default_folder(&self) -> Result<FolderConfiguration> {
+        self.require_version(&CONFIG_API_VERSION)?;
         log::debug!("GET /config/defaults/folder");
         Ok(self
             .client
@@ -432,28 +1345,107 @@ impl Client {
             .await?)
     }
 
-    /// Calculates the data synchronization completion percentage and counts.
-    ///
-    /// Returns the completion percentage (0 to 100), total bytes, and total items.
-    ///
-    /// # Arguments
+    /// Returns the global [`OptionsConfiguration`].
+    pub async fn get_options(&self) -> Result<OptionsConfiguration> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("GET /config/options");
+        Ok(self
+            .client
+            .get(format!("{}/config/options", self.base_url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Replaces the global options with `options`.
     ///
-    /// * `folder_id` - The ID of the folder to calculate completion for.
-    ///   `None` calculates the aggregate completion across all folders.
-    /// * `device_id` - The ID of the device to calculate completion for.
-    ///   `None` calculates the aggregate completion across all devices.
-    ///   If `device_id` is specified but `folder_id` is `None`,
-    ///   completion is calculated for all folders *shared with that device*.
-    pub async fn get_completion(
-        &self,
-        folder_id: Option<&str>,
-        device_id: Option<&str>,
-    ) -> Result<Completion> {
-        let folder_str = match folder_id {
-            Some(folder_id) => format!("folder={folder_id}"),
-            None => String::new(),
-        };
-        let device_str = match device_id {
+    /// Use [`patch_options`](crate::client::Client::patch_options) if only
+    /// some of the options should be changed.
+    pub async fn put_options(&self, options: &OptionsConfiguration) -> Result<()> {
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PUT /config/options {options:?}");
+        self.client
+            .put(format!("{}/config/options", self.base_url))
+            .json(options)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Changes only the global options which are set in `options`,
+    /// all others keep their current value.
+    pub async fn patch_options(&self, options: impl Into<NewOptionsConfiguration>) -> Result<()> {
+        let options = options.into();
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PATCH /config/options {options:?}");
+        self.client
+            .patch(format!("{}/config/options", self.base_url))
+            .json(&options)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Changes only the GUI settings which are set in `gui`,
+    /// all others keep their current value.
+    pub async fn patch_gui(&self, gui: impl Into<NewGuiConfiguration>) -> Result<()> {
+        let gui = gui.into();
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PATCH /config/gui {gui:?}");
+        self.client
+            .patch(format!("{}/config/gui", self.base_url))
+            .json(&gui)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Changes only the LDAP settings which are set in `ldap`,
+    /// all others keep their current value.
+    pub async fn patch_ldap(&self, ldap: impl Into<NewLDAPConfiguration>) -> Result<()> {
+        let ldap = ldap.into();
+        self.require_version(&CONFIG_API_VERSION)?;
+        log::debug!("PATCH /config/ldap {ldap:?}");
+        self.client
+            .patch(format!("{}/config/ldap", self.base_url))
+            .json(&ldap)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Calculates the data synchronization completion percentage and counts.
+    ///
+    /// Returns the completion percentage (0 to 100), total bytes, and total items.
+    ///
+    /// # Arguments
+    ///
+    /// * `folder_id` - The ID of the folder to calculate completion for.
+    ///   `None` calculates the aggregate completion across all folders.
+    /// * `device_id` - The ID of the device to calculate completion for.
+    ///   `None` calculates the aggregate completion across all devices.
+    ///   If `device_id` is specified but `folder_id` is `None`,
+    ///   completion is calculated for all folders *shared with that device*.
+    pub async fn get_completion(
+        &self,
+        folder_id: Option<&str>,
+        device_id: Option<&DeviceId>,
+    ) -> Result<Completion> {
+        let folder_str = match folder_id {
+            Some(folder_id) => format!("folder={}", encode_query(folder_id)),
+            None => String::new(),
+        };
+        let device_str = match device_id {
             Some(device_id) => format!("device={device_id}"),
             None => String::new(),
         };
@@ -479,14 +1471,351 @@ impl Client {
             .json()
             .await?)
     }
+
+    /// Returns information about the current status of the folder with the ID `folder_id`.
+    ///
+    /// This is an expensive call, increasing CPU and RAM usage on the device.
+    /// Use sparingly.
+    pub async fn get_folder_status(&self, folder_id: &str) -> Result<FolderSummary> {
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/status?folder={folder_id}");
+        Ok(self
+            .client
+            .get(format!("{}/db/status?folder={}", self.base_url, folder_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the files which are needed by this device in order for the folder
+    /// with the ID `folder_id` to become in sync. If `pagination` is `None`, the
+    /// default page size of Syncthing is used.
+    ///
+    /// Use [`need_pages`](crate::client::Client::need_pages) to walk every page.
+    pub async fn get_need(&self, folder_id: &str, pagination: Option<Pagination>) -> Result<Need> {
+        let page_str = pagination.map(|p| p.query()).unwrap_or_default();
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/need?folder={folder_id}{page_str}");
+        Ok(self
+            .client
+            .get(format!(
+                "{}/db/need?folder={}{}",
+                self.base_url, folder_id, page_str
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns a stream over every page of [`get_need`](crate::client::Client::get_need),
+    /// with `per_page` files on each page, but at least one.
+    pub fn need_pages(&self, folder_id: &str, per_page: u64) -> impl Stream<Item = Result<Need>> {
+        let folder_id = folder_id.to_string();
+        paginate(per_page, move |pagination| {
+            let folder_id = folder_id.clone();
+            async move { self.get_need(&folder_id, Some(pagination)).await }
+        })
+    }
+
+    /// Returns the files which are needed by the device with the ID `device_id`
+    /// in order for the folder with the ID `folder_id` to become in sync.
+    /// If `pagination` is `None`, the default page size of Syncthing is used.
+    ///
+    /// Use [`remote_need_pages`](crate::client::Client::remote_need_pages) to walk every page.
+    pub async fn get_remote_need(
+        &self,
+        folder_id: &str,
+        device_id: &DeviceId,
+        pagination: Option<Pagination>,
+    ) -> Result<FilePage> {
+        let page_str = pagination.map(|p| p.query()).unwrap_or_default();
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/remoteneed?folder={folder_id}&device={device_id}{page_str}");
+        Ok(self
+            .client
+            .get(format!(
+                "{}/db/remoteneed?folder={}&device={}{}",
+                self.base_url, folder_id, device_id, page_str
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns a stream over every page of
+    /// [`get_remote_need`](crate::client::Client::get_remote_need),
+    /// with `per_page` files on each page, but at least one.
+    pub fn remote_need_pages(
+        &self,
+        folder_id: &str,
+        device_id: &DeviceId,
+        per_page: u64,
+    ) -> impl Stream<Item = Result<FilePage>> {
+        let folder_id = folder_id.to_string();
+        let device_id = *device_id;
+        paginate(per_page, move |pagination| {
+            let folder_id = folder_id.clone();
+            async move {
+                self.get_remote_need(&folder_id, &device_id, Some(pagination))
+                    .await
+            }
+        })
+    }
+
+    /// Returns the files which have been changed locally in the receive-only
+    /// folder with the ID `folder_id`. If `pagination` is `None`, the default
+    /// page size of Syncthing is used.
+    ///
+    /// Use [`local_changed_pages`](crate::client::Client::local_changed_pages) to walk every page.
+    pub async fn get_local_changed(
+        &self,
+        folder_id: &str,
+        pagination: Option<Pagination>,
+    ) -> Result<FilePage> {
+        let page_str = pagination.map(|p| p.query()).unwrap_or_default();
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/localchanged?folder={folder_id}{page_str}");
+        Ok(self
+            .client
+            .get(format!(
+                "{}/db/localchanged?folder={}{}",
+                self.base_url, folder_id, page_str
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the directory tree of the folder with the ID `folder_id`, as it is
+    /// known to the database. This does not touch the disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Only return the tree below this path.
+    /// * `levels` - How deep to descend into the tree, where `0` only returns
+    ///   the direct children. `None` returns the whole tree.
+    pub async fn browse(
+        &self,
+        folder_id: &str,
+        prefix: Option<&str>,
+        levels: Option<u64>,
+    ) -> Result<Vec<BrowseEntry>> {
+        let prefix_str = match prefix {
+            Some(prefix) => format!("&prefix={}", encode_query(prefix)),
+            None => String::new(),
+        };
+        let levels_str = match levels {
+            Some(levels) => format!("&levels={levels}"),
+            None => String::new(),
+        };
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/browse?folder={folder_id}{prefix_str}{levels_str}");
+        Ok(self
+            .client
+            .get(format!(
+                "{}/db/browse?folder={}{}{}",
+                self.base_url, folder_id, prefix_str, levels_str
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the local and global version of the file at the path `file`
+    /// within the folder with the ID `folder_id`. Explicitly returns a
+    /// [`UnknownFileError`](crate::error::Error::UnknownFileError) if the
+    /// file is not in the index.
+    pub async fn get_file(&self, folder_id: &str, file: &str) -> Result<FileDetails> {
+        let file = encode_query(file);
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/file?folder={folder_id}&file={file}");
+        let response = self
+            .client
+            .get(format!(
+                "{}/db/file?folder={}&file={}",
+                self.base_url, folder_id, file
+            ))
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            Err(Error::UnknownFileError)
+        } else {
+            Ok(response.error_for_status()?.json().await?)
+        }
+    }
+
+    /// Requests an immediate rescan of the folder with the ID `folder_id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `sub` - Only rescan these paths within the folder. An empty slice
+    ///   rescans the whole folder.
+    /// * `next` - Delay the next scheduled scan by this many seconds.
+    pub async fn scan_folder(
+        &self,
+        folder_id: &str,
+        sub: &[&str],
+        next: Option<u64>,
+    ) -> Result<()> {
+        let sub_str: String = sub
+            .iter()
+            .map(|sub| format!("&sub={}", encode_query(sub)))
+            .collect();
+        let next_str = match next {
+            Some(next) => format!("&next={next}"),
+            None => String::new(),
+        };
+        let folder_id = encode_query(folder_id);
+        log::debug!("POST /db/scan?folder={folder_id}{sub_str}{next_str}");
+        self.client
+            .post(format!(
+                "{}/db/scan?folder={}{}{}",
+                self.base_url, folder_id, sub_str, next_str
+            ))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Makes the local state of the send-only folder with the ID `folder_id`
+    /// authoritative, overriding any changes made on remote devices.
+    ///
+    /// Returns an [`UnsupportedFolderTypeError`](crate::error::Error::UnsupportedFolderTypeError)
+    /// for any other folder type. This requires an additional check against the API.
+    pub async fn override_folder(&self, folder_id: &str) -> Result<()> {
+        let folder = self.get_folder(folder_id).await?;
+        if folder.folder_type != FolderType::SendOnly {
+            return Err(Error::UnsupportedFolderTypeError(folder.folder_type));
+        }
+        let folder_id = encode_query(folder_id);
+        log::debug!("POST /db/override?folder={folder_id}");
+        self.client
+            .post(format!(
+                "{}/db/override?folder={}",
+                self.base_url, folder_id
+            ))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Reverts all local changes of the receive-only folder with the ID `folder_id`,
+    /// restoring the global state.
+    ///
+    /// Returns an [`UnsupportedFolderTypeError`](crate::error::Error::UnsupportedFolderTypeError)
+    /// for any other folder type. This requires an additional check against the API.
+    pub async fn revert_folder(&self, folder_id: &str) -> Result<()> {
+        let folder = self.get_folder(folder_id).await?;
+        if !matches!(
+            folder.folder_type,
+            FolderType::ReceiveOnly | FolderType::ReceiveEncrypted
+        ) {
+            return Err(Error::UnsupportedFolderTypeError(folder.folder_type));
+        }
+        let folder_id = encode_query(folder_id);
+        log::debug!("POST /db/revert?folder={folder_id}");
+        self.client
+            .post(format!("{}/db/revert?folder={}", self.base_url, folder_id))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    /// Moves the file at the path `file` within the folder with the ID `folder_id`
+    /// to the top of the download queue. Returns the updated need list, just like
+    /// [`get_need`](crate::client::Client::get_need).
+    pub async fn prioritize_file(&self, folder_id: &str, file: &str) -> Result<Need> {
+        let file = encode_query(file);
+        let folder_id = encode_query(folder_id);
+        log::debug!("POST /db/prio?folder={folder_id}&file={file}");
+        Ok(self
+            .client
+            .post(format!(
+                "{}/db/prio?folder={}&file={}",
+                self.base_url, folder_id, file
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns the ignore patterns of the folder with the ID `folder_id`.
+    pub async fn get_ignores(&self, folder_id: &str) -> Result<FolderIgnores> {
+        let folder_id = encode_query(folder_id);
+        log::debug!("GET /db/ignores?folder={folder_id}");
+        Ok(self
+            .client
+            .get(format!("{}/db/ignores?folder={}", self.base_url, folder_id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Replaces the `.stignore` file of the folder with the ID `folder_id` with
+    /// `lines`, and returns the resulting ignore patterns.
+    ///
+    /// Use [`IgnoreMatcher`](crate::ignore::IgnoreMatcher) to validate the lines beforehand.
+    pub async fn set_ignores(&self, folder_id: &str, lines: Vec<String>) -> Result<FolderIgnores> {
+        let folder_id = encode_query(folder_id);
+        log::debug!("POST /db/ignores?folder={folder_id} {lines:?}");
+        Ok(self
+            .client
+            .post(format!("{}/db/ignores?folder={}", self.base_url, folder_id))
+            .json(&std::collections::HashMap::from([("ignore", lines)]))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Returns a stream over every page of
+    /// [`get_local_changed`](crate::client::Client::get_local_changed),
+    /// with `per_page` files on each page, but at least one.
+    pub fn local_changed_pages(
+        &self,
+        folder_id: &str,
+        per_page: u64,
+    ) -> impl Stream<Item = Result<FilePage>> {
+        let folder_id = folder_id.to_string();
+        paginate(per_page, move |pagination| {
+            let folder_id = folder_id.clone();
+            async move { self.get_local_changed(&folder_id, Some(pagination)).await }
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::types::{config::FolderDeviceConfiguration, events::EventType};
+    use crate::types::{
+        config::FolderDeviceConfiguration,
+        events::{EventType, EventTypeKind},
+    };
 
     use super::*;
 
+    use futures::{StreamExt, TryStreamExt};
     use httpmock::prelude::*;
     use testcontainers::{
         ContainerAsync, GenericImage, ImageExt,
@@ -500,6 +1829,10 @@ mod tests {
     // Example device id from the docs
     const DEVICE_ID: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
 
+    fn device_id() -> DeviceId {
+        DEVICE_ID.parse().unwrap()
+    }
+
     #[fixture]
     async fn syncthing_setup() -> (ContainerAsync<GenericImage>, Client) {
         let api_key = "foobar";
@@ -554,54 +1887,1520 @@ mod tests {
         let result = client.ping().await;
         ping_mock.assert();
 
-        assert!(result.is_ok());
+        assert!(result.is_ok());
+    }
+
+    /// Simple test ensuring that a single event in the past is correctly
+    /// transmitted.
+    #[tokio::test]
+    async fn test_single_event() {
+        let server = MockServer::start();
+        mock_status(&server, START_TIME).await;
+
+        let event_mock = server.mock(|when, then| {
+            when.method(GET).path("/events");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(
+                    r#"
+[
+  {
+    "id": 1,
+    "globalID": 1,
+    "time": "2025-05-07T17:05:44.514050967+02:00",
+    "type": "Starting",
+    "data": {
+      "home": "/home/user/.config/syncthing",
+      "myID": "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD"
+    }
+  }
+]
+"#,
+                );
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let (tx, mut rx) = broadcast::channel(1);
+
+        // Start transmitting events on a separate thread
+        tokio::spawn(async move {
+            let result = client.get_events(tx, false).await;
+            unreachable!("get_events should not have returned: {:?}", result);
+        });
+
+        let event = rx.recv().await;
+        assert!(event_mock.calls() > 0);
+        assert!(event.is_ok());
+        assert!(matches!(event.unwrap().ty, EventType::Starting { .. }));
+    }
+
+    /// Builds the JSON of `Starting` events with the given IDs.
+    fn events_json(ids: &[u64]) -> String {
+        let events: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "id": id,
+                    "globalID": id,
+                    "time": "2025-05-07T17:05:44.514050967+02:00",
+                    "type": "Starting",
+                    "data": {
+                        "home": "/var/syncthing/config",
+                        "myID": "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD"
+                    }
+                })
+            })
+            .collect();
+        serde_json::to_string(&events).unwrap()
+    }
+
+    async fn mock_events<'a>(
+        server: &'a MockServer,
+        since: &str,
+        ids: &[u64],
+    ) -> httpmock::Mock<'a> {
+        let body = events_json(ids);
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/events")
+                    .query_param("since", since)
+                    .query_param_missing("limit");
+                then.status(200).body(body);
+            })
+            .await
+    }
+
+    async fn mock_latest_event<'a>(server: &'a MockServer, id: u64) -> httpmock::Mock<'a> {
+        let body = events_json(&[id]);
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/events")
+                    .query_param("since", "0")
+                    .query_param("limit", "1")
+                    .query_param("timeout", "0");
+                then.status(200).body(body);
+            })
+            .await
+    }
+
+    const START_TIME: &str = "2025-05-07T17:05:40.123456789+02:00";
+
+    async fn mock_status<'a>(server: &'a MockServer, start_time: &str) -> httpmock::Mock<'a> {
+        let body = status_json(start_time);
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/system/status");
+                then.status(200).body(body);
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        let server = MockServer::start_async().await;
+        let mock = |since: &'static str, ids: &'static [u64]| {
+            server.mock_async(move |when, then| {
+                when.method(GET)
+                    .path("/events")
+                    .query_param("since", since)
+                    .query_param_missing("limit")
+                    .query_param("timeout", "5");
+                then.status(200).body(events_json(ids));
+            })
+        };
+        let status = mock_status(&server, START_TIME).await;
+        let first = mock("0", &[1, 2]).await;
+        let second = mock("2", &[3]).await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client.events().timeout(Duration::from_secs(5));
+        assert_eq!(events.last_id(), 0);
+        for id in 1..=3 {
+            assert_eq!(events.try_next().await.unwrap().unwrap().id, id);
+            assert_eq!(events.last_id(), id);
+        }
+        status.assert_async().await;
+        first.assert_async().await;
+        second.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_kinds() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let filtered = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/events")
+                    .query_param("since", "0")
+                    .query_param("events", "Starting,StateChanged");
+                then.status(200).body(events_json(&[1]));
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client
+            .events()
+            .kinds([EventTypeKind::Starting, EventTypeKind::StateChanged]);
+        let event = events.try_next().await.unwrap().unwrap();
+        assert_eq!(event.ty.kind(), EventTypeKind::Starting);
+        filtered.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_disk_events() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let disk = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/events/disk")
+                    .query_param("since", "0");
+                then.status(200).body(
+                    r#"[{
+                        "id": 1,
+                        "globalID": 84,
+                        "time": "2025-05-07T17:05:44.514050967+02:00",
+                        "type": "RemoteChangeDetected",
+                        "data": {
+                            "type": "file",
+                            "action": "deleted",
+                            "folder": "default",
+                            "folderID": "default",
+                            "path": "foo/bar.txt",
+                            "label": "Default Folder",
+                            "modifiedBy": "MFZWI3D"
+                        }
+                    }]"#,
+                );
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client.disk_events();
+        let event = events.try_next().await.unwrap().unwrap();
+        assert!(matches!(
+            event.ty,
+            EventType::RemoteChangeDetected { path, .. } if path == "foo/bar.txt"
+        ));
+        assert_eq!(events.last_id(), 1);
+        disk.assert_async().await;
+    }
+
+    /// Invalid and unknown events do not end the stream.
+    #[tokio::test]
+    async fn test_event_stream_invalid_events() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let mut events: Vec<serde_json::Value> =
+            serde_json::from_str(&events_json(&[1, 2, 3, 4])).unwrap();
+        events[1]["data"]["home"] = 42.into();
+        events[2]["type"] = "SomethingNew".into();
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/events").query_param("since", "0");
+                then.status(200).json_body(serde_json::Value::from(events));
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client.events();
+        assert_eq!(events.next().await.unwrap().unwrap().id, 1);
+        assert!(matches!(
+            events.next().await,
+            Some(Err(Error::InvalidEventError { id: 2, .. }))
+        ));
+        assert_eq!(events.last_id(), 2);
+        let unknown = events.next().await.unwrap().unwrap();
+        assert!(matches!(unknown.ty, EventType::Unknown { ty, .. } if ty == "SomethingNew"));
+        assert_eq!(events.next().await.unwrap().unwrap().id, 4);
+        mock.assert_async().await;
+    }
+
+    /// After a transient error, the stream continues after the last event.
+    #[tokio::test]
+    async fn test_event_stream_retry() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let unavailable = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/events").query_param("since", "2");
+                then.status(503);
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let latest = mock_latest_event(&server, 2).await;
+        let events = client.events().since(2);
+        assert_eq!(events.last_id(), 2);
+
+        let next = tokio::spawn(async move {
+            let mut events = events;
+            events.try_next().await.unwrap().unwrap()
+        });
+        while unavailable.calls_async().await == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        unavailable.delete_async().await;
+        let resumed = mock_events(&server, "2", &[3]).await;
+
+        assert_eq!(next.await.unwrap().id, 3);
+        // Once when starting and once after the error
+        latest.assert_calls_async(2).await;
+        resumed.assert_async().await;
+    }
+
+    /// Syncthing restarted and already has more events than were received
+    /// before, which is only noticed by the changed start time.
+    #[tokio::test]
+    async fn test_event_stream_restart() {
+        let server = MockServer::start_async().await;
+        let status = mock_status(&server, START_TIME).await;
+        mock_latest_event(&server, 2).await;
+        let unavailable = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/events").query_param("since", "2");
+                then.status(503);
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let next = tokio::spawn(async move {
+            let mut events = client.events().since(2);
+            events.try_next().await.unwrap().unwrap()
+        });
+        while unavailable.calls_async().await == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        unavailable.delete_async().await;
+        status.delete_async().await;
+        let restarted_status = mock_status(&server, "2025-05-07T17:10:00+02:00").await;
+        let restarted = mock_events(&server, "0", &[1, 2, 3]).await;
+
+        assert_eq!(next.await.unwrap().id, 1);
+        restarted_status.assert_async().await;
+        restarted.assert_async().await;
+    }
+
+    /// Long polls which time out without events do not check for a restart.
+    #[tokio::test]
+    async fn test_event_stream_idle() {
+        let server = MockServer::start_async().await;
+        let status = mock_status(&server, START_TIME).await;
+        let latest = mock_latest_event(&server, 2).await;
+        let idle = mock_events(&server, "2", &[]).await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let next = tokio::spawn(async move {
+            let mut events = client.events().since(2);
+            events.try_next().await
+        });
+        while idle.calls_async().await < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        next.abort();
+        status.assert_async().await;
+        latest.assert_async().await;
+    }
+
+    /// Syncthing restarted, so the IDs start at `1` again.
+    #[tokio::test]
+    async fn test_event_stream_reset() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let latest = mock_latest_event(&server, 2).await;
+        let restarted = mock_events(&server, "0", &[1, 2]).await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client.events().since(42);
+        assert_eq!(events.try_next().await.unwrap().unwrap().id, 1);
+        assert_eq!(events.last_id(), 1);
+        latest.assert_async().await;
+        restarted.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_error() {
+        let server = MockServer::start_async().await;
+        mock_status(&server, START_TIME).await;
+        let forbidden = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/events");
+                then.status(403);
+            })
+            .await;
+
+        let client = ClientBuilder::new("")
+            .base_url(server.url(""))
+            .build()
+            .unwrap();
+        let mut events = client.events();
+        assert!(matches!(
+            events.next().await,
+            Some(Err(Error::NetworkError(_)))
+        ));
+        assert!(events.next().await.is_none());
+        forbidden.assert_async().await;
+    }
+
+    /// Builds the JSON of a file as returned by the db endpoints.
+    fn file_json(name: &str) -> String {
+        format!(
+            r#"
+{{
+  "deleted": false,
+  "ignored": false,
+  "inodeChange": "1970-01-01T01:00:00+01:00",
+  "invalid": false,
+  "localFlags": 0,
+  "modified": "2025-05-07T17:05:44.514050967+02:00",
+  "modifiedBy": "MFZWI3D",
+  "mustRescan": false,
+  "name": "{name}",
+  "noPermissions": false,
+  "numBlocks": 1,
+  "permissions": "0644",
+  "sequence": 3,
+  "size": 12,
+  "type": "FILE_INFO_TYPE_FILE",
+  "version": ["MFZWI3D:1"]
+}}
+"#
+        )
+    }
+
+    /// Walking all pages of the need list should stop at the first page
+    /// which is not completely filled.
+    #[tokio::test]
+    async fn test_need_pages() {
+        let server = MockServer::start();
+
+        let first_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/need")
+                .query_param("folder", "default")
+                .query_param("page", "1")
+                .query_param("perpage", "2");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{"progress": [{}], "queued": [{}], "rest": [], "page": 1, "perpage": 2}}"#,
+                    file_json("a"),
+                    file_json("b")
+                ));
+        });
+
+        let second_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/need")
+                .query_param("folder", "default")
+                .query_param("page", "2")
+                .query_param("perpage", "2");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{"progress": [], "queued": [], "rest": [{}], "page": 2, "perpage": 2}}"#,
+                    file_json("c")
+                ));
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let pages: Vec<Need> = client.need_pages("default", 2).try_collect().await.unwrap();
+
+        first_mock.assert();
+        second_mock.assert();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].progress[0].name, "a");
+        assert_eq!(pages[0].queued[0].name, "b");
+        assert_eq!(pages[1].rest[0].name, "c");
+    }
+
+    /// A completely filled last page requires one more request, whose empty
+    /// result must not be part of the stream.
+    #[tokio::test]
+    async fn test_local_changed_pages_full() {
+        let server = MockServer::start();
+
+        let first_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/localchanged")
+                .query_param("page", "1")
+                .query_param("perpage", "1");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"{{"files": [{}], "page": 1, "perpage": 1}}"#,
+                    file_json("a")
+                ));
+        });
+
+        let second_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/localchanged")
+                .query_param("page", "2")
+                .query_param("perpage", "1");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{"files": [], "page": 2, "perpage": 1}"#);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        // At least one item is requested per page
+        let pages: Vec<FilePage> = client
+            .local_changed_pages("default", 0)
+            .try_collect()
+            .await
+            .unwrap();
+
+        first_mock.assert();
+        second_mock.assert();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].files[0].name, "a");
+    }
+
+    /// The file path must be encoded, and both versions of the file parsed.
+    #[tokio::test]
+    async fn test_get_file() {
+        let server = MockServer::start();
+
+        let file_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/file")
+                .query_param("folder", "default")
+                .query_param("file", "foo/bar & baz.txt");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(format!(
+                    r#"
+{{
+  "availability": [{{"id": "{DEVICE_ID}", "fromTemporary": false}}],
+  "global": {},
+  "local": {},
+  "mtime": {{
+    "err": null,
+    "value": {{"real": "0001-01-01T00:00:00Z", "virtual": "0001-01-01T00:00:00Z"}}
+  }}
+}}
+"#,
+                    file_json("foo/bar & baz.txt"),
+                    file_json("foo/bar & baz.txt")
+                ));
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let file = client
+            .get_file("default", "foo/bar & baz.txt")
+            .await
+            .unwrap();
+
+        file_mock.assert();
+        assert_eq!(file.availability.unwrap()[0].id, device_id());
+        assert_eq!(file.local.name, "foo/bar & baz.txt");
+        assert_eq!(
+            file.local.version.compare(&file.global.version),
+            Some(std::cmp::Ordering::Equal)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_file_unknown() {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(GET).path("/db/file");
+            then.status(404).body("No such object in the index");
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let result = client.get_file("default", "missing").await;
+        assert!(matches!(result, Err(Error::UnknownFileError)));
+    }
+
+    /// Folder IDs may contain characters which are special in a query.
+    #[tokio::test]
+    async fn test_encode_folder_id() {
+        let server = MockServer::start();
+
+        let browse_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/browse")
+                .query_param("folder", "a&b #c")
+                .query_param("prefix", "foo");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body("[]");
+        });
+        let need_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/db/need")
+                .query_param("folder", "a&b #c")
+                .query_param("page", "1")
+                .query_param("perpage", "10");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{"progress": [], "queued": [], "rest": [], "page": 1, "perpage": 10}"#);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client.browse("a&b #c", Some("foo"), None).await.unwrap();
+        client
+            .get_need("a&b #c", Some(Pagination::new(1, 10)))
+            .await
+            .unwrap();
+        browse_mock.assert();
+        need_mock.assert();
+    }
+
+    /// Every sub path must be passed as its own query parameter.
+    #[tokio::test]
+    async fn test_scan_sub_paths() {
+        let server = MockServer::start();
+
+        let scan_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/db/scan")
+                .query_param("folder", "default")
+                .query_param("sub", "foo")
+                .query_param("sub", "bar baz")
+                .query_param("next", "60");
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client
+            .scan_folder("default", &["foo", "bar baz"], Some(60))
+            .await
+            .unwrap();
+
+        scan_mock.assert();
+    }
+
+    /// Only the options which are set must be sent.
+    #[tokio::test]
+    async fn test_patch_options() {
+        let server = MockServer::start();
+
+        let patch_mock = server.mock(|when, then| {
+            when.method(PATCH)
+                .path("/config/options")
+                .json_body(serde_json::json!({"relaysEnabled": false}));
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client
+            .patch_options(NewOptionsConfiguration::new().relays_enabled(false))
+            .await
+            .unwrap();
+
+        patch_mock.assert();
+    }
+
+    /// Only the set fields must be sent, so e.g. the path is not rewritten.
+    #[tokio::test]
+    async fn test_patch_folder() {
+        let server = MockServer::start();
+
+        let patch_mock = server.mock(|when, then| {
+            when.method(PATCH)
+                .path("/config/folders/default")
+                .json_body(serde_json::json!({"paused": true}));
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client
+            .patch_folder("default", FolderConfigurationPatch::new().paused(true))
+            .await
+            .unwrap();
+
+        patch_mock.assert();
+    }
+
+    /// The ID in the body must not differ from the patched one.
+    #[tokio::test]
+    async fn test_patch_mismatched_id() {
+        let server = MockServer::start();
+
+        let patch_mock = server.mock(|when, then| {
+            when.method(PATCH);
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let result = client
+            .patch_folder(
+                "default",
+                FolderConfigurationPatch::new().id("other".to_string()),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::MismatchedIdError { .. })));
+
+        let other: DeviceId = "6ZPJ5NK-PIUNKHW-UZ5RQB4-GVJC76Z-GLPVVFO-LQW5MZ4-KQR3UKK-D4JPEAQ"
+            .parse()
+            .unwrap();
+        let result = client
+            .patch_device(
+                &device_id(),
+                DeviceConfigurationPatch::new().device_id(other),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::MismatchedIdError { .. })));
+
+        // The same ID is allowed
+        client
+            .patch_device(
+                &device_id(),
+                DeviceConfigurationPatch::new().device_id(device_id()),
+            )
+            .await
+            .unwrap();
+        patch_mock.assert_calls(1);
+    }
+
+    #[tokio::test]
+    async fn test_patch_unknown_device() {
+        let server = MockServer::start();
+
+        let patch_mock = server.mock(|when, then| {
+            when.method(PATCH)
+                .path(format!("/config/devices/{DEVICE_ID}"))
+                .json_body(serde_json::json!({"name": "foo"}));
+            then.status(404);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let result = client
+            .patch_device(
+                &device_id(),
+                DeviceConfigurationPatch::new().name("foo".to_string()),
+            )
+            .await;
+
+        assert!(matches!(result, Err(Error::UnknownDeviceError)));
+        patch_mock.assert();
+    }
+
+    fn config_json() -> String {
+        std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.json"),
+        )
+        .unwrap()
+    }
+
+    /// A configuration which was changed after reading it must not be overwritten.
+    #[tokio::test]
+    async fn test_put_configuration_conflict() {
+        let server = MockServer::start();
+
+        let mut get_mock = server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(config_json());
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT).path("/config");
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let (config, revision) = client.get_configuration_with_revision().await.unwrap();
+
+        // Someone else changes the configuration in the meantime
+        get_mock.delete();
+        server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(config_json().replace(r#""theme": "default""#, r#""theme": "dark""#));
+        });
+
+        let result = client.put_configuration(&config, &revision).await;
+        assert!(matches!(
+            result,
+            Err(Error::ConfigurationConflictError { ref expected, ref found })
+                if *expected == revision && found.version == revision.version
+        ));
+        put_mock.assert_calls(0);
+    }
+
+    /// Fields which are not part of `Configuration` must be written back.
+    #[tokio::test]
+    async fn test_put_configuration_unknown_fields() {
+        let server = MockServer::start();
+
+        let mut raw: serde_json::Value = serde_json::from_str(&config_json()).unwrap();
+        raw["futureSection"] = serde_json::json!({"enabled": true});
+        raw["options"]["futureOption"] = serde_json::json!(42);
+        raw["folders"][0]["futureField"] = serde_json::json!("keep");
+        server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(raw.to_string());
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT).path("/config").json_body_includes(
+                serde_json::json!({
+                    "futureSection": {"enabled": true},
+                    "options": {"futureOption": 42, "relaysEnabled": false},
+                    "folders": [{"id": "default", "futureField": "keep"}]
+                })
+                .to_string(),
+            );
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client
+            .modify_configuration(|config| config.options.relays_enabled = false)
+            .await
+            .unwrap();
+        put_mock.assert();
+    }
+
+    /// Removed map entries are not restored from the current configuration.
+    #[tokio::test]
+    async fn test_modify_configuration_remove_param() {
+        let server = MockServer::start();
+
+        let mut raw: serde_json::Value = serde_json::from_str(&config_json()).unwrap();
+        raw["folders"][0]["versioning"]["params"] =
+            serde_json::json!({"keep": "5", "cleanoutDays": "1"});
+        server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(raw.to_string());
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/config")
+                .body_includes(r#""params":{"cleanoutDays":"1"}"#)
+                .body_excludes(r#""keep""#);
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client
+            .modify_configuration(|config| {
+                config.folders[0].versioning.params.remove("keep");
+            })
+            .await
+            .unwrap();
+        put_mock.assert();
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let original = serde_json::json!({
+            "devices": [{"deviceID": "a", "x": 1}, {"deviceID": "b", "x": 2}],
+            "list": [{"y": 1}],
+            "params": {"a": "1", "b": "2"},
+            "unknown": true
+        });
+        // Elements with an ID are matched even if they were reordered
+        let edited = serde_json::json!({
+            "devices": [{"deviceID": "b"}, {"deviceID": "c"}],
+            "list": [{"z": 2}],
+            "params": {"b": "3"}
+        });
+        assert_eq!(
+            merge_json(&original, edited),
+            serde_json::json!({
+                "devices": [{"deviceID": "b", "x": 2}, {"deviceID": "c"}],
+                "list": [{"y": 1, "z": 2}],
+                "params": {"b": "3"},
+                "unknown": true
+            })
+        );
+    }
+
+    /// A conflicting modification has to be retried with the new configuration.
+    #[tokio::test]
+    async fn test_modify_configuration_retry() {
+        let server = MockServer::start();
+
+        let mut get_mock = Some(server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(config_json());
+        }));
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT)
+                .path("/config")
+                .body_includes(r#""theme":"dark""#)
+                .body_includes(r#""relaysEnabled":false"#);
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let mut calls = 0;
+        let config = client
+            .modify_configuration(|config| {
+                calls += 1;
+                // Someone else changes the configuration while we modify it
+                if let Some(mut mock) = get_mock.take() {
+                    mock.delete();
+                    server.mock(|when, then| {
+                        when.method(GET).path("/config");
+                        then.status(200)
+                            .header("content-type", "application/json")
+                            .body(
+                                config_json()
+                                    .replace(r#""theme": "default""#, r#""theme": "dark""#),
+                            );
+                    });
+                }
+                config.options.relays_enabled = false;
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(calls, 2);
+        assert_eq!(config.gui.theme, "dark");
+        assert!(!config.options.relays_enabled);
+        put_mock.assert_calls(1);
+    }
+
+    /// Nothing should be written if the configuration is unchanged.
+    #[tokio::test]
+    async fn test_modify_configuration_unchanged() {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(config_json());
+        });
+        let put_mock = server.mock(|when, then| {
+            when.method(PUT).path("/config");
+            then.status(200);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        client.modify_configuration(|_| {}).await.unwrap();
+        put_mock.assert_calls(0);
+    }
+
+    /// Builds a minimal `/system/status` response with the given start time.
+    fn status_json(start_time: &str) -> String {
+        serde_json::json!({
+            "alloc": 0,
+            "connectionServiceStatus": {},
+            "discoveryEnabled": false,
+            "goroutines": 1,
+            "myID": DEVICE_ID,
+            "pathSeparator": "/",
+            "startTime": start_time,
+            "sys": 0,
+            "themes": [],
+            "tilde": "/var/syncthing",
+            "uptime": 1,
+            "urVersionMax": 3
+        })
+        .to_string()
+    }
+
+    /// A restart is observed by the new start time, even if the API never
+    /// stopped answering in between two polls.
+    #[tokio::test]
+    async fn test_restart_and_wait() {
+        let server = MockServer::start();
+
+        let restart_mock = server.mock(|when, then| {
+            when.method(POST).path("/system/restart");
+            then.status(200);
+        });
+        let health_mock = server.mock(|when, then| {
+            when.method(GET).path("/noauth/health");
+            then.status(200).body(r#"{"status": "OK"}"#);
+        });
+        // Only the start time is read
+        let mut old_mock = server.mock(|when, then| {
+            when.method(GET).path("/system/status");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(r#"{"startTime": "2025-05-07T17:05:44.514050967+02:00"}"#);
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let (result, new_mock) =
+            tokio::join!(client.restart_and_wait(Duration::from_secs(5)), async {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                old_mock.delete();
+                server.mock(|when, then| {
+                    when.method(GET).path("/system/status");
+                    then.status(200)
+                        .header("content-type", "application/json")
+                        .body(status_json("2025-05-07T17:06:01.123456789+02:00"));
+                })
+            });
+
+        result.unwrap();
+        restart_mock.assert();
+        assert!(health_mock.calls() > 0);
+        assert!(new_mock.calls() > 0);
+    }
+
+    /// If the start time never changes, the restart cannot be observed.
+    #[tokio::test]
+    async fn test_restart_and_wait_timeout() {
+        let server = MockServer::start();
+
+        server.mock(|when, then| {
+            when.method(POST).path("/system/restart");
+            then.status(200);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/noauth/health");
+            then.status(200).body(r#"{"status": "OK"}"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/system/status");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(status_json("2025-05-07T17:05:44.514050967+02:00"));
+        });
+
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap();
+
+        let result = client.restart_and_wait(Duration::from_millis(300)).await;
+        assert!(matches!(result, Err(Error::TimeoutError)));
+    }
+
+    #[test]
+    fn test_gui_base_url() {
+        let url = |address, use_tls| gui_base_url(address, use_tls).unwrap();
+        assert_eq!(url("127.0.0.1:8384", false), "http://127.0.0.1:8384/rest");
+        assert_eq!(url("0.0.0.0:8384", true), "https://127.0.0.1:8384/rest");
+        assert_eq!(url(":8384", false), "http://127.0.0.1:8384/rest");
+        assert_eq!(url("[::]:8384", false), "http://[::1]:8384/rest");
+        assert_eq!(url("https://[::1]:8443", false), "https://[::1]:8443/rest");
+        assert_eq!(url("http://host:8384", true), "http://host:8384/rest");
+        assert_eq!(
+            url("/run/syncthing.sock", false),
+            "unix:///run/syncthing.sock"
+        );
+        assert_eq!(
+            url("/run/syncthing.sock", true),
+            "unixs:///run/syncthing.sock"
+        );
+        assert_eq!(
+            url("unix:///run/syncthing.sock", false),
+            "unix:///run/syncthing.sock"
+        );
+        assert!(matches!(
+            gui_base_url("ftp://host:21", false),
+            Err(Error::UnsupportedGuiAddressError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_from_config_file() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.xml");
+        let server = MockServer::start_async().await;
+        let mock = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/rest/system/ping")
+                    .header("X-API-KEY", "WW3qcEcsmKuf9ZMJ3ymzF7orm4yVHQQj");
+                then.status(200).body(r#"{"ping": "pong"}"#);
+            })
+            .await;
+
+        let builder = ClientBuilder::from_config_file(&path).unwrap();
+        assert_eq!(
+            builder.base_url.as_deref(),
+            Some("http://127.0.0.1:8384/rest")
+        );
+        let client = builder.base_url(server.url("/rest")).build().unwrap();
+        client.ping().await.unwrap();
+        mock.assert_async().await;
+    }
+
+    /// Requests are sent over the socket, with the API key and the usual paths.
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let path = std::env::temp_dir().join(format!("syncthing-rs-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut buf = [0; 1024];
+                let n = stream.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "connection closed");
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"ping": "pong"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\ncontent-type: application/json\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let client = Client::builder("secret")
+            .base_url(format!("unix://{}", path.display()))
+            .build()
+            .unwrap();
+        client.ping().await.unwrap();
+
+        let request = server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(request.starts_with("GET /rest/system/ping HTTP/1.1\r\n"));
+        assert!(request.to_lowercase().contains("x-api-key: secret\r\n"));
+    }
+
+    /// Serves `{"ping": "pong"}` via HTTPS, using the self-signed GUI certificate
+    /// from the test data, and returns its base URL.
+    async fn serve_tls() -> String {
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let data = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data");
+        let cert = CertificateDer::from_pem_file(data.join("https-cert.pem")).unwrap();
+        let key = PrivateKeyDer::from_pem_file(data.join("https-key.pem")).unwrap();
+        let config = rustls::ServerConfig::builder_with_provider(Arc::new(
+            rustls::crypto::aws_lc_rs::default_provider(),
+        ))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert], key)
+        .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                // Rejected handshakes are expected
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    continue;
+                };
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf).await;
+                let body = r#"{"ping": "pong"}"#;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        format!("https://{addr}/rest")
+    }
+
+    /// SHA-256 fingerprint of `tests/data/https-cert.pem`.
+    const FINGERPRINT: &str = "0E:73:FF:0E:1C:03:B6:F0:0E:37:DD:C8:AE:5F:E5:4D:5C:1C:BD:10:3F:11:AD:CA:D0:16:20:69:29:77:E2:46";
+
+    #[tokio::test]
+    async fn test_tls_options() {
+        let url = serve_tls().await;
+        let builder = || {
+            Client::builder("secret")
+                .base_url(&url)
+                .timeout(Duration::from_secs(10))
+        };
+
+        // The self-signed certificate is rejected by default
+        let client = builder().build().unwrap();
+        assert!(matches!(client.ping().await, Err(Error::NetworkError(_))));
+
+        let client = builder().pin_certificate(FINGERPRINT).build().unwrap();
+        client.ping().await.unwrap();
+
+        let other = FINGERPRINT.replace("0E:73", "0E:74");
+        let client = builder().pin_certificate(other).build().unwrap();
+        assert!(matches!(client.ping().await, Err(Error::NetworkError(_))));
+
+        let pem = std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/https-cert.pem"),
+        )
+        .unwrap();
+        let client = builder().add_root_certificate(pem).build().unwrap();
+        client.ping().await.unwrap();
+
+        let client = builder().danger_accept_invalid_certs(true).build().unwrap();
+        client.ping().await.unwrap();
+    }
+
+    /// Pinning would silently override the other options.
+    #[test]
+    fn test_conflicting_tls_options() {
+        assert!(matches!(
+            Client::builder("secret")
+                .pin_certificate(FINGERPRINT)
+                .danger_accept_invalid_certs(true)
+                .build(),
+            Err(Error::ConflictingTlsOptionsError(_))
+        ));
+        assert!(matches!(
+            Client::builder("secret")
+                .add_root_certificate(
+                    std::fs::read(
+                        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                            .join("tests/data/https-cert.pem"),
+                    )
+                    .unwrap(),
+                )
+                .pin_certificate(FINGERPRINT)
+                .build(),
+            Err(Error::ConflictingTlsOptionsError(_))
+        ));
+        // Disabling the other options is fine
+        assert!(
+            Client::builder("secret")
+                .pin_certificate(FINGERPRINT)
+                .danger_accept_invalid_certs(false)
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_invalid_tls_options() {
+        assert!(matches!(
+            Client::builder("secret").pin_certificate("0E:73").build(),
+            Err(Error::InvalidCertificateError(_))
+        ));
+        assert!(matches!(
+            Client::builder("secret")
+                .add_root_certificate("not a certificate")
+                .build(),
+            Err(Error::InvalidCertificateError(_))
+        ));
+        assert!(matches!(
+            Client::builder("secret").proxy("not a url").build(),
+            Err(Error::NetworkError(_))
+        ));
+    }
+
+    /// Mocks the GUI page, which hands out the CSRF token.
+    async fn mock_csrf(server: &MockServer) -> httpmock::Mock<'_> {
+        server
+            .mock_async(|when, then| {
+                when.method(GET).path("/");
+                then.status(200)
+                    .header("set-cookie", "CSRF-Token-ABCDE=csrf; Path=/");
+            })
+            .await
+    }
+
+    async fn mock_login<'a>(server: &'a MockServer, session: &str) -> httpmock::Mock<'a> {
+        server
+            .mock_async(|when, then| {
+                when.method(POST)
+                    .path("/rest/noauth/auth/password")
+                    .header("X-CSRF-Token-ABCDE", "csrf")
+                    .json_body(serde_json::json!({
+                        "username": "user",
+                        "password": "pass",
+                        "stayLoggedIn": false,
+                    }));
+                then.status(204).header(
+                    "set-cookie",
+                    format!("sessionid-ABCDE={session}; Path=/; HttpOnly"),
+                );
+            })
+            .await
+    }
+
+    async fn mock_ping<'a>(
+        server: &'a MockServer,
+        session: &str,
+        status: u16,
+    ) -> httpmock::Mock<'a> {
+        server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/rest/system/ping")
+                    .header("X-CSRF-Token-ABCDE", "csrf")
+                    .header("cookie", format!("sessionid-ABCDE={session}"));
+                then.status(status).body(r#"{"ping": "pong"}"#);
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_session_auth() {
+        let server = MockServer::start_async().await;
+        let csrf = mock_csrf(&server).await;
+        let login = mock_login(&server, "first").await;
+        let ping = mock_ping(&server, "first", 200).await;
+
+        let client = ClientBuilder::with_password("user", "pass")
+            .base_url(server.url("/rest"))
+            .build()
+            .unwrap();
+        client.ping().await.unwrap();
+        client.ping().await.unwrap();
+        csrf.assert_async().await;
+        login.assert_async().await;
+        ping.assert_calls_async(2).await;
+
+        // The session expired, so the client has to log in again
+        login.delete_async().await;
+        ping.delete_async().await;
+        let expired = mock_ping(&server, "first", 403).await;
+        let login = mock_login(&server, "second").await;
+        let ping = mock_ping(&server, "second", 200).await;
+
+        client.ping().await.unwrap();
+        expired.assert_async().await;
+        login.assert_async().await;
+        ping.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_session_auth_invalid_password() {
+        let server = MockServer::start_async().await;
+        mock_csrf(&server).await;
+        let login = server
+            .mock_async(|when, then| {
+                when.method(POST).path("/rest/noauth/auth/password");
+                then.status(403);
+            })
+            .await;
+
+        let client = ClientBuilder::with_password("user", "wrong")
+            .base_url(server.url("/rest"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.ping().await,
+            Err(Error::AuthenticationError)
+        ));
+        login.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth() {
+        let server = MockServer::start_async().await;
+        let csrf = mock_csrf(&server).await;
+        let ping = server
+            .mock_async(|when, then| {
+                when.method(GET)
+                    .path("/rest/system/ping")
+                    .header("X-CSRF-Token-ABCDE", "csrf")
+                    // user:pass
+                    .header("authorization", "Basic dXNlcjpwYXNz")
+                    .header_missing("X-API-KEY");
+                then.status(200).body(r#"{"ping": "pong"}"#);
+            })
+            .await;
+
+        let client = ClientBuilder::with_basic_auth("user", "pass")
+            .base_url(server.url("/rest"))
+            .build()
+            .unwrap();
+        client.ping().await.unwrap();
+        client.ping().await.unwrap();
+        csrf.assert_async().await;
+        ping.assert_calls_async(2).await;
+    }
+
+    #[tokio::test]
+    async fn test_basic_auth_invalid_password() {
+        let server = MockServer::start_async().await;
+        let csrf = mock_csrf(&server).await;
+        let ping = server
+            .mock_async(|when, then| {
+                when.method(GET).path("/rest/system/ping");
+                then.status(401);
+            })
+            .await;
+
+        let client = ClientBuilder::with_basic_auth("user", "wrong")
+            .base_url(server.url("/rest"))
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.ping().await,
+            Err(Error::AuthenticationError)
+        ));
+        // The request is repeated once with a new CSRF token
+        csrf.assert_calls_async(2).await;
+        ping.assert_calls_async(2).await;
     }
 
-    /// Simple test ensuring that a single event in the past is correctly
-    /// transmitted.
+    /// The version should be fetched once while building and cached afterwards.
     #[tokio::test]
-    async fn test_single_event() {
+    async fn test_build_with_version() {
         let server = MockServer::start();
 
-        let event_mock = server.mock(|when, then| {
-            when.method(GET).path("/events");
+        let version_mock = server.mock(|when, then| {
+            when.method(GET).path("/system/version");
             then.status(200)
                 .header("content-type", "application/json")
                 .body(
                     r#"
-[
-  {
-    "id": 1,
-    "globalID": 1,
-    "time": "2025-05-07T17:05:44.514050967+02:00",
-    "type": "Starting",
-    "data": {
-      "home": "/home/user/.config/syncthing",
-      "myID": "XXXXXXX-XXXXXXX-XXXXXXX-XXXXXXX-XXXXXXX-XXXXXXX-XXXXXXX-XXXXXXX"
-    }
-  }
-]
+{
+  "arch": "amd64",
+  "codename": "Fermium Flea",
+  "isBeta": false,
+  "isCandidate": false,
+  "isRelease": true,
+  "longVersion": "syncthing v1.10.0 \"Fermium Flea\" (go1.15.1 darwin-amd64) jb@kvin.kastelo.net 2020-09-15 20:05:13 UTC [stnoupgrade]",
+  "os": "darwin",
+  "stamp": "1600200313",
+  "tags": ["noupgrade"],
+  "user": "jb@kvin.kastelo.net",
+  "version": "v1.10.0"
+}
 "#,
                 );
         });
 
         let client = ClientBuilder::new("")
             .base_url(server.base_url())
-            .build()
+            .build_with_version()
+            .await
             .unwrap();
 
-        let (tx, mut rx) = broadcast::channel(1);
+        assert_eq!(
+            client.syncthing_version().await.unwrap(),
+            SyncthingVersion::new(1, 10, 0)
+        );
+        version_mock.assert_calls(1);
+
+        // v1.10.0 does not know about the config endpoints yet
+        let result = client.get_configuration().await;
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedVersionError { required, .. }) if *required == CONFIG_API_VERSION
+        ));
+    }
 
-        // Start transmitting events on a separate thread
-        tokio::spawn(async move {
-            let result = client.get_events(tx, false).await;
-            unreachable!("get_events should not have returned: {:?}", result);
+    /// A set version must not result in any request, even if the endpoint
+    /// is not supported.
+    #[tokio::test]
+    async fn test_unsupported_version() {
+        let server = MockServer::start();
+
+        let pending_mock = server.mock(|when, then| {
+            when.method(GET).path("/cluster/pending/devices");
+            then.status(404);
         });
 
-        let event = rx.recv().await;
-        assert!(event_mock.calls() > 0);
-        assert!(event.is_ok());
-        assert!(matches!(event.unwrap().ty, EventType::Starting { home: _ }));
+        let client = ClientBuilder::new("")
+            .base_url(server.base_url())
+            .version(SyncthingVersion::new(1, 12, 3))
+            .build()
+            .unwrap();
+
+        let result = client.get_pending_devices().await;
+        assert!(matches!(result, Err(Error::UnsupportedVersionError { .. })));
+        pending_mock.assert_calls(0);
     }
 
     #[tokio::test]
@@ -801,7 +3600,7 @@ mod tests {
     ) {
         let (_container, client) = syncthing_setup.await;
 
-        let device = NewDeviceConfiguration::new(DEVICE_ID.to_string());
+        let device = NewDeviceConfiguration::new(device_id());
 
         client
             .post_device(device)
@@ -809,11 +3608,11 @@ mod tests {
             .expect("could not post device");
 
         let api_device = client
-            .get_device(DEVICE_ID)
+            .get_device(&device_id())
             .await
             .expect("could not get device");
 
-        assert_eq!(&api_device.device_id, DEVICE_ID);
+        assert_eq!(api_device.device_id, device_id());
     }
 
     #[rstest]
@@ -823,7 +3622,7 @@ mod tests {
     ) {
         let (_container, client) = syncthing_setup.await;
 
-        let device = NewDeviceConfiguration::new(DEVICE_ID.to_string());
+        let device = NewDeviceConfiguration::new(device_id());
 
         client
             .add_device(device)
@@ -831,11 +3630,11 @@ mod tests {
             .expect("could not add device");
 
         let api_device = client
-            .get_device(DEVICE_ID)
+            .get_device(&device_id())
             .await
             .expect("could not get device");
 
-        assert_eq!(&api_device.device_id, DEVICE_ID);
+        assert_eq!(api_device.device_id, device_id());
     }
 
     #[rstest]
@@ -846,7 +3645,7 @@ mod tests {
     ) {
         let (_container, client) = syncthing_setup.await;
 
-        let device = NewDeviceConfiguration::new(DEVICE_ID.to_string());
+        let device = NewDeviceConfiguration::new(device_id());
 
         client
             .add_device(device)
@@ -854,7 +3653,7 @@ mod tests {
             .expect("could not add device");
 
         // "Accidentally" overwrite our device
-        let duplicate_device = NewDeviceConfiguration::new(DEVICE_ID.to_string());
+        let duplicate_device = NewDeviceConfiguration::new(device_id());
 
         client
             .add_device(duplicate_device)
@@ -870,7 +3669,7 @@ mod tests {
         let (_container, client) = syncthing_setup.await;
         let name = "original";
 
-        let device = NewDeviceConfiguration::new(DEVICE_ID.to_string()).name(name.to_string());
+        let device = NewDeviceConfiguration::new(device_id()).name(name.to_string());
 
         client
             .add_device(device)
@@ -880,7 +3679,7 @@ mod tests {
         // "Accidentally" overwrite our device
         let duplicate_name = "duplicate";
         let duplicate_device =
-            NewDeviceConfiguration::new(DEVICE_ID.to_string()).name(duplicate_name.to_string());
+            NewDeviceConfiguration::new(device_id()).name(duplicate_name.to_string());
 
         client
             .post_device(duplicate_device)
@@ -888,11 +3687,11 @@ mod tests {
             .expect("could not post device");
 
         let api_device = client
-            .get_device(DEVICE_ID)
+            .get_device(&device_id())
             .await
             .expect("could not get device");
 
-        assert_eq!(&api_device.device_id, DEVICE_ID);
+        assert_eq!(api_device.device_id, device_id());
         assert_eq!(&api_device.name, duplicate_name);
     }
 
@@ -1046,10 +3845,11 @@ mod tests {
     ) {
         let (_container, client) = syncthing_setup.await;
 
-        client
+        let device = client
             .get_default_device()
             .await
             .expect("could not get default device");
+        assert_eq!(device.device_id, None);
     }
 
     #[rstest]
@@ -1124,7 +3924,7 @@ mod tests {
 
         // First client accepts the device
         first_client
-            .add_device(NewDeviceConfiguration::new(second_id.clone()))
+            .add_device(NewDeviceConfiguration::new(second_id))
             .await
             .expect("could not add device");
 
@@ -1178,7 +3978,7 @@ mod tests {
 
         // Add the first device to the second
         second_client
-            .add_device(NewDeviceConfiguration::new(first_id.clone()))
+            .add_device(NewDeviceConfiguration::new(first_id))
             .await
             .expect("could not add device");
 
@@ -1203,7 +4003,7 @@ mod tests {
 
         // First client accepts the device
         first_client
-            .add_device(NewDeviceConfiguration::new(second_id.clone()))
+            .add_device(NewDeviceConfiguration::new(second_id))
             .await
             .expect("could not add device");
 
@@ -1213,7 +4013,7 @@ mod tests {
 
         let folder_on_first = NewFolderConfiguration::new(folder_id.to_string(), path.to_string())
             .devices(vec![FolderDeviceConfiguration {
-                device_id: second_id.clone(),
+                device_id: second_id,
                 introduced_by: String::new(),
                 encryption_password: String::new(),
             }]);
@@ -1302,7 +4102,7 @@ mod tests {
     ) {
         let (_container, client) = syncthing_setup.await;
 
-        let device = NewDeviceConfiguration::new(DEVICE_ID.to_string());
+        let device = NewDeviceConfiguration::new(device_id());
 
         client
             .post_device(device)
@@ -1310,7 +4110,7 @@ mod tests {
             .expect("could not post device");
 
         let api_device = client
-            .get_device(DEVICE_ID)
+            .get_device(&device_id())
             .await
             .expect("could not get device");
 
@@ -1321,10 +4121,10 @@ mod tests {
             .devices
             .len();
 
-        assert_eq!(&api_device.device_id, DEVICE_ID);
+        assert_eq!(api_device.device_id, device_id());
 
         client
-            .delete_device(DEVICE_ID)
+            .delete_device(&device_id())
             .await
             .expect("could not delete folder");
 
@@ -1335,4 +4135,406 @@ mod tests {
 
         assert_eq!(config.devices.len(), num_devices - 1);
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_folder_status(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let folder_id = "this-is-a-new-folder";
+        let path = "/tmp";
+
+        let folder = NewFolderConfiguration::new(folder_id.to_string(), path.to_string());
+
+        client
+            .post_folder(folder)
+            .await
+            .expect("could not post folder");
+
+        client
+            .get_folder_status(folder_id)
+            .await
+            .expect("could not get folder status");
+
+        let need = client
+            .get_need(folder_id, None)
+            .await
+            .expect("could not get need");
+        assert_eq!(need.page, 1);
+
+        let pages: Vec<Need> = client
+            .need_pages(folder_id, 10)
+            .try_collect()
+            .await
+            .expect("could not walk need pages");
+        assert!(pages.is_empty());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_browse(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let folder_id = "this-is-a-new-folder";
+        let path = "/tmp";
+
+        let folder = NewFolderConfiguration::new(folder_id.to_string(), path.to_string());
+
+        client
+            .post_folder(folder)
+            .await
+            .expect("could not post folder");
+
+        client
+            .browse(folder_id, None, Some(1))
+            .await
+            .expect("could not browse folder");
+
+        let result = client.get_file(folder_id, "does-not-exist").await;
+        assert!(matches!(result, Err(Error::UnknownFileError)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_ignores(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let folder_id = "this-is-a-new-folder";
+        let path = "/tmp";
+
+        let folder = NewFolderConfiguration::new(folder_id.to_string(), path.to_string());
+
+        client
+            .post_folder(folder)
+            .await
+            .expect("could not post folder");
+
+        let lines = vec!["!/keep.tmp".to_string(), "*.tmp".to_string()];
+        let ignores = client
+            .set_ignores(folder_id, lines.clone())
+            .await
+            .expect("could not set ignores");
+        assert_eq!(ignores.ignore, Some(lines.clone()));
+
+        let ignores = client
+            .get_ignores(folder_id)
+            .await
+            .expect("could not get ignores");
+        assert_eq!(ignores.ignore, Some(lines));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_folder_actions(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let folder_id = "this-is-a-new-folder";
+        let path = "/tmp";
+
+        let folder = NewFolderConfiguration::new(folder_id.to_string(), path.to_string())
+            .folder_type(FolderType::ReceiveOnly);
+
+        client
+            .post_folder(folder)
+            .await
+            .expect("could not post folder");
+
+        client
+            .scan_folder(folder_id, &[], None)
+            .await
+            .expect("could not scan folder");
+
+        client
+            .revert_folder(folder_id)
+            .await
+            .expect("could not revert folder");
+
+        let result = client.override_folder(folder_id).await;
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedFolderTypeError(FolderType::ReceiveOnly))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_restart(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+
+        assert!(
+            !client
+                .is_restart_required()
+                .await
+                .expect("could not get restart required")
+        );
+
+        client
+            .restart_and_wait(Duration::from_secs(30))
+            .await
+            .expect("could not restart");
+
+        client.ping().await.unwrap();
+    }
+
+    /// All events sent by Syncthing can be parsed, including the whole
+    /// configuration sent by `ConfigSaved`.
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_event_samples(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        client
+            .post_folder(NewFolderConfiguration::new(
+                "samples".to_string(),
+                "/tmp/samples".to_string(),
+            ))
+            .await
+            .expect("could not post folder");
+
+        let query = EventQuery {
+            timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        let mut saved = None;
+        for event in client.fetch_events(&query).await.unwrap() {
+            let event = event.expect("could not parse event");
+            if let EventType::ConfigSaved(config) = event.ty {
+                saved = Some(config);
+            }
+        }
+        assert_eq!(
+            *saved.expect("no ConfigSaved event"),
+            client.get_configuration().await.unwrap()
+        );
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_system_info(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+
+        let status = client
+            .get_system_status()
+            .await
+            .expect("could not get system status");
+        assert_eq!(status.my_id, client.get_id().await.unwrap());
+
+        let version = client.get_version().await.expect("could not get version");
+        assert!(version.long_version.contains(&version.version));
+
+        client.get_paths().await.expect("could not get paths");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_options(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+
+        let mut options = client.get_options().await.expect("could not get options");
+        assert_eq!(client.get_configuration().await.unwrap().options, options);
+
+        client
+            .patch_options(NewOptionsConfiguration::new().max_recv_kbps(100))
+            .await
+            .expect("could not patch options");
+        options.max_recv_kbps = 100;
+        assert_eq!(client.get_options().await.unwrap(), options);
+
+        options.relays_enabled = !options.relays_enabled;
+        client
+            .put_options(&options)
+            .await
+            .expect("could not put options");
+        assert_eq!(client.get_options().await.unwrap(), options);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_patch_folder_and_device(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let folder_id = "this-is-a-new-folder";
+        let path = "/tmp";
+
+        client
+            .post_folder(NewFolderConfiguration::new(
+                folder_id.to_string(),
+                path.to_string(),
+            ))
+            .await
+            .expect("could not post folder");
+        client
+            .post_device(NewDeviceConfiguration::new(device_id()).name("foo".to_string()))
+            .await
+            .expect("could not post device");
+
+        client
+            .patch_folder(
+                folder_id,
+                FolderConfigurationPatch::new().label("bar".to_string()),
+            )
+            .await
+            .expect("could not patch folder");
+        client
+            .patch_device(&device_id(), DeviceConfigurationPatch::new().paused(true))
+            .await
+            .expect("could not patch device");
+
+        let folder = client.get_folder(folder_id).await.unwrap();
+        assert_eq!(folder.label, "bar");
+        assert_eq!(folder.path, path);
+
+        let device = client.get_device(&device_id()).await.unwrap();
+        assert!(device.paused);
+        // Untouched fields must keep their value
+        assert_eq!(device.name, "foo");
+
+        let result = client
+            .patch_folder(
+                "does-not-exist",
+                FolderConfigurationPatch::new().label("bar".to_string()),
+            )
+            .await;
+        assert!(matches!(result, Err(Error::UnknownFolderError)));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_patch_gui_and_ldap(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let before = client.get_configuration().await.unwrap();
+
+        client
+            .patch_gui(NewGuiConfiguration::new().theme("dark".to_string()))
+            .await
+            .expect("could not patch gui");
+        client
+            .patch_ldap(NewLDAPConfiguration::new().address("ldap.example.com:389".to_string()))
+            .await
+            .expect("could not patch ldap");
+
+        let after = client.get_configuration().await.unwrap();
+        assert_eq!(after.gui.theme, "dark");
+        assert_eq!(after.gui.api_key, before.gui.api_key);
+        assert_eq!(after.ldap.address, "ldap.example.com:389");
+        assert_eq!(after.ldap.transport, before.ldap.transport);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_password_auth(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        // Syncthing hashes the password when it is set
+        client
+            .patch_gui(
+                NewGuiConfiguration::new()
+                    .user("user".to_string())
+                    .password("pass".to_string()),
+            )
+            .await
+            .expect("could not set GUI credentials");
+
+        for builder in [
+            ClientBuilder::with_password("user", "pass"),
+            ClientBuilder::with_basic_auth("user", "pass"),
+        ] {
+            let password_client = builder.base_url(&client.base_url).build().unwrap();
+            password_client
+                .ping()
+                .await
+                .expect("could not authenticate");
+            assert_eq!(
+                password_client.get_configuration().await.unwrap().gui.user,
+                "user"
+            );
+        }
+
+        for builder in [
+            ClientBuilder::with_password("user", "wrong"),
+            ClientBuilder::with_basic_auth("user", "wrong"),
+        ] {
+            let result = builder
+                .base_url(&client.base_url)
+                .build()
+                .unwrap()
+                .ping()
+                .await;
+            assert!(matches!(result, Err(Error::AuthenticationError)));
+        }
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_modify_configuration(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let (config, revision) = client.get_configuration_with_revision().await.unwrap();
+
+        let modified = client
+            .modify_configuration(|config| config.options.max_send_kbps = 42)
+            .await
+            .expect("could not modify configuration");
+        assert_eq!(client.get_configuration().await.unwrap(), modified);
+
+        // The revision read before is outdated now
+        let result = client.put_configuration(&config, &revision).await;
+        assert!(matches!(
+            result,
+            Err(Error::ConfigurationConflictError { .. })
+        ));
+        assert_eq!(client.get_options().await.unwrap().max_send_kbps, 42);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn container_test_reconcile(
+        #[future] syncthing_setup: (ContainerAsync<GenericImage>, Client),
+    ) {
+        let (_container, client) = syncthing_setup.await;
+        let desired = crate::reconcile::DesiredState {
+            folders: vec![
+                NewFolderConfiguration::new("reconciled".to_string(), "/tmp".to_string())
+                    .label("Reconciled".to_string()),
+            ],
+            devices: vec![NewDeviceConfiguration::new(device_id()).name("foo".to_string())],
+            prune: true,
+        };
+
+        let existing = client.get_configuration().await.unwrap().folders.len();
+        let plan = desired
+            .reconcile(&client, true)
+            .await
+            .expect("could not plan");
+        // Creating both and deleting all existing folders
+        assert_eq!(plan.actions.len(), 2 + existing);
+        assert!(client.get_folder("reconciled").await.is_err());
+
+        desired
+            .reconcile(&client, false)
+            .await
+            .expect("could not reconcile");
+        let config = client.get_configuration().await.unwrap();
+        assert_eq!(config.folders.len(), 1);
+        assert_eq!(config.folders[0].label, "Reconciled");
+
+        // Applying is idempotent
+        assert!(desired.plan(&client).await.unwrap().is_empty());
+    }
 }
@@ -0,0 +1,1195 @@
+//! Reader and writer for Syncthing's on-disk `config.xml`.
+//!
+//! This allows to inspect and edit the [`Configuration`] while Syncthing is
+//! not running, using the same types as the REST [`Client`](crate::Client).
+//! Elements and attributes which are not part of [`Configuration`] are
+//! ignored when reading, but kept by [`to_file`] when it overwrites an
+//! existing file.
+//!
+//! ```no_run
+//! # fn example() -> syncthing_rs::error::Result<()> {
+//! use syncthing_rs::config_xml;
+//!
+//! let mut config = config_xml::from_file("/var/syncthing/config/config.xml")?;
+//! config.options.relays_enabled = false;
+//! config_xml::to_file("/var/syncthing/config/config.xml", &config)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The XML schema differs from the JSON one used by the REST API, e.g. some
+//! fields are stored as attributes and lists are stored as repeated elements
+//! with singular names. Internally, the XML is translated from and to the
+//! JSON representation, so the `serde` implementations of the types are
+//! reused.
+use std::{
+    borrow::Cow,
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use quick_xml::{
+    Reader, Writer, XmlVersion,
+    escape::unescape,
+    events::{BytesStart, BytesText, Event},
+};
+use serde::{
+    Deserialize,
+    de::{
+        self, IntoDeserializer, Unexpected, Visitor,
+        value::{MapDeserializer, SeqDeserializer},
+    },
+    forward_to_deserialize_any,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{Error, Result},
+    types::config::{Configuration, GuiConfiguration},
+};
+
+/// How a JSON field is represented as a child element.
+enum Kind {
+    /// A single element containing only text.
+    Text,
+    /// An element per item, each containing only text.
+    TextList,
+    /// A single element with the given layout.
+    Object(&'static Element),
+    /// An element per item with the given layout.
+    List(&'static Element),
+    /// An empty element per entry, storing key and value in the given attributes.
+    Map {
+        key: &'static str,
+        value: &'static str,
+    },
+}
+
+/// Layout of an XML element. All JSON fields which are not mentioned are
+/// stored as [`Kind::Text`] children with the same name.
+struct Element {
+    /// Fields stored as attributes, as `(xml, json)` names.
+    attributes: &'static [(&'static str, &'static str)],
+    /// Field stored as the text content of the element itself.
+    text: Option<&'static str>,
+    /// Children which differ from the default, as `(xml, json, kind)`.
+    children: &'static [(&'static str, &'static str, Kind)],
+    /// Values of fields Syncthing omits if they are empty.
+    defaults: &'static [(&'static str, &'static str)],
+}
+
+impl Element {
+    const fn new() -> Self {
+        Self {
+            attributes: &[],
+            text: None,
+            children: &[],
+            defaults: &[],
+        }
+    }
+
+    fn child_by_xml(&self, name: &str) -> Option<(&'static str, &Kind)> {
+        self.children
+            .iter()
+            .find(|(xml, _, _)| *xml == name)
+            .map(|(_, json, kind)| (*json, kind))
+    }
+
+    fn child_by_json(&self, name: &str) -> Option<(&'static str, &Kind)> {
+        self.children
+            .iter()
+            .find(|(_, json, _)| *json == name)
+            .map(|(xml, _, kind)| (*xml, kind))
+    }
+}
+
+const CONFIGURATION: Element = Element {
+    attributes: &[("version", "version")],
+    children: &[
+        ("folder", "folders", Kind::List(&FOLDER)),
+        ("device", "devices", Kind::List(&DEVICE)),
+        ("gui", "gui", Kind::Object(&GUI)),
+        ("ldap", "ldap", Kind::Object(&LDAP)),
+        ("options", "options", Kind::Object(&OPTIONS)),
+        (
+            "remoteIgnoredDevice",
+            "remoteIgnoredDevices",
+            Kind::List(&OBSERVED_DEVICE),
+        ),
+        ("defaults", "defaults", Kind::Object(&DEFAULTS)),
+    ],
+    ..Element::new()
+};
+
+const FOLDER: Element = Element {
+    attributes: &[
+        ("id", "id"),
+        ("label", "label"),
+        ("path", "path"),
+        ("type", "type"),
+        ("rescanIntervalS", "rescanIntervalS"),
+        ("fsWatcherEnabled", "fsWatcherEnabled"),
+        ("fsWatcherDelayS", "fsWatcherDelayS"),
+        ("fsWatcherTimeoutS", "fsWatcherTimeoutS"),
+        ("ignorePerms", "ignorePerms"),
+        ("autoNormalize", "autoNormalize"),
+    ],
+    children: &[
+        ("device", "devices", Kind::List(&FOLDER_DEVICE)),
+        ("minDiskFree", "minDiskFree", Kind::Object(&SIZE)),
+        ("versioning", "versioning", Kind::Object(&VERSIONING)),
+        ("xattrFilter", "xattrFilter", Kind::Object(&XATTR_FILTER)),
+    ],
+    ..Element::new()
+};
+
+const FOLDER_DEVICE: Element = Element {
+    attributes: &[("id", "deviceID"), ("introducedBy", "introducedBy")],
+    defaults: &[("encryptionPassword", "")],
+    ..Element::new()
+};
+
+const SIZE: Element = Element {
+    attributes: &[("unit", "unit")],
+    text: Some("value"),
+    ..Element::new()
+};
+
+const VERSIONING: Element = Element {
+    attributes: &[("type", "type")],
+    children: &[(
+        "param",
+        "params",
+        Kind::Map {
+            key: "key",
+            value: "val",
+        },
+    )],
+    defaults: &[("type", "")],
+    ..Element::new()
+};
+
+const XATTR_FILTER: Element = Element {
+    children: &[("entry", "entries", Kind::List(&XATTR_FILTER_ENTRY))],
+    ..Element::new()
+};
+
+const XATTR_FILTER_ENTRY: Element = Element {
+    attributes: &[("match", "match"), ("permit", "permit")],
+    ..Element::new()
+};
+
+const DEVICE: Element = Element {
+    attributes: &[
+        ("id", "deviceID"),
+        ("name", "name"),
+        ("compression", "compression"),
+        ("introducer", "introducer"),
+        ("skipIntroductionRemovals", "skipIntroductionRemovals"),
+        ("introducedBy", "introducedBy"),
+    ],
+    children: &[
+        ("address", "addresses", Kind::TextList),
+        ("allowedNetwork", "allowedNetworks", Kind::TextList),
+        (
+            "ignoredFolder",
+            "ignoredFolders",
+            Kind::List(&OBSERVED_FOLDER),
+        ),
+    ],
+    defaults: &[("name", ""), ("certName", "")],
+    ..Element::new()
+};
+
+const OBSERVED_FOLDER: Element = Element {
+    attributes: &[("time", "time"), ("id", "id"), ("label", "label")],
+    ..Element::new()
+};
+
+const OBSERVED_DEVICE: Element = Element {
+    attributes: &[
+        ("time", "time"),
+        ("id", "deviceID"),
+        ("name", "name"),
+        ("address", "address"),
+    ],
+    ..Element::new()
+};
+
+const GUI: Element = Element {
+    attributes: &[
+        ("enabled", "enabled"),
+        ("tls", "useTLS"),
+        ("debugging", "debugging"),
+        ("sendBasicAuthPrompt", "sendBasicAuthPrompt"),
+    ],
+    children: &[("apikey", "apiKey", Kind::Text)],
+    defaults: &[
+        ("unixSocketPermissions", ""),
+        ("user", ""),
+        ("password", ""),
+        ("authMode", "static"),
+        ("apiKey", ""),
+        ("insecureAdminAccess", "false"),
+        ("insecureSkipHostcheck", "false"),
+        ("insecureAllowFrameLoading", "false"),
+    ],
+    ..Element::new()
+};
+
+const LDAP: Element = Element {
+    defaults: &[
+        ("address", ""),
+        ("bindDN", ""),
+        ("transport", "plain"),
+        ("insecureSkipVerify", "false"),
+        ("searchBaseDN", ""),
+        ("searchFilter", ""),
+    ],
+    ..Element::new()
+};
+
+const OPTIONS: Element = Element {
+    children: &[
+        ("listenAddress", "listenAddresses", Kind::TextList),
+        (
+            "globalAnnounceServer",
+            "globalAnnounceServers",
+            Kind::TextList,
+        ),
+        ("urUniqueID", "urUniqueId", Kind::Text),
+        ("minHomeDiskFree", "minHomeDiskFree", Kind::Object(&SIZE)),
+        ("alwaysLocalNet", "alwaysLocalNets", Kind::TextList),
+        (
+            "unackedNotificationID",
+            "unackedNotificationIDs",
+            Kind::TextList,
+        ),
+        ("crashReportingURL", "crURL", Kind::Text),
+        ("stunServer", "stunServers", Kind::TextList),
+        ("featureFlag", "featureFlags", Kind::TextList),
+    ],
+    ..Element::new()
+};
+
+const DEFAULTS: Element = Element {
+    children: &[
+        ("folder", "folder", Kind::Object(&FOLDER)),
+        ("device", "device", Kind::Object(&DEVICE)),
+        ("ignores", "ignores", Kind::Object(&IGNORES)),
+    ],
+    ..Element::new()
+};
+
+const IGNORES: Element = Element {
+    children: &[("line", "lines", Kind::TextList)],
+    ..Element::new()
+};
+
+/// Parses the content of a `config.xml` file.
+///
+/// # Errors
+///
+/// This method fails if `xml` is not well-formed or does not
+/// contain a valid configuration.
+pub fn from_str(xml: &str) -> Result<Configuration> {
+    let root = Node::parse_configuration(xml)?;
+    Configuration::deserialize(Text(root.to_json(&CONFIGURATION)))
+        .map_err(|e| Error::InvalidConfigXmlError(e.to_string()))
+}
+
+/// Parses only the `<gui>` element of a `config.xml` file, so other
+/// sections do not need to match the layout of this crate.
+pub(crate) fn gui_from_str(xml: &str) -> Result<GuiConfiguration> {
+    let root = Node::parse_configuration(xml)?;
+    let gui = root
+        .children
+        .iter()
+        .find(|child| child.name == "gui")
+        .ok_or_else(|| Error::InvalidConfigXmlError("no <gui> element".to_string()))?;
+    GuiConfiguration::deserialize(Text(gui.to_json(&GUI)))
+        .map_err(|e| Error::InvalidConfigXmlError(e.to_string()))
+}
+
+/// Reads and parses the `config.xml` file at `path`.
+///
+/// # Errors
+///
+/// This method fails if the file cannot be read or is invalid, see [`from_str`].
+pub fn from_file(path: impl AsRef<Path>) -> Result<Configuration> {
+    from_str(&std::fs::read_to_string(path)?)
+}
+
+/// Formats `config` in the layout of a `config.xml` file.
+///
+/// # Errors
+///
+/// This method fails if `config` cannot be represented as JSON, which
+/// is the intermediate format.
+pub fn to_string(config: &Configuration) -> Result<String> {
+    write_configuration(config, None)
+}
+
+/// Formats `config` like [`to_string`], but keeps all elements and
+/// attributes of the existing `config.xml` content `xml` which are not
+/// part of [`Configuration`], e.g. those added by newer Syncthing versions.
+///
+/// # Errors
+///
+/// This method fails if `xml` is not well-formed, see also [`to_string`].
+pub fn to_string_preserving(xml: &str, config: &Configuration) -> Result<String> {
+    let original = Node::parse_configuration(xml)?;
+    write_configuration(config, Some(&original))
+}
+
+/// Writes `config` to the `config.xml` file at `path`. If the file already
+/// exists, its unknown elements and attributes are kept, see
+/// [`to_string_preserving`].
+///
+/// The file is replaced atomically, so it is never left partially written.
+/// It keeps its permissions, and a new file is only readable by its owner,
+/// as it contains the API key and the GUI password.
+///
+/// # Errors
+///
+/// This method fails if the file cannot be read or written, see also
+/// [`to_string_preserving`].
+pub fn to_file(path: impl AsRef<Path>, config: &Configuration) -> Result<()> {
+    let path = path.as_ref();
+    let (xml, permissions) = match std::fs::read_to_string(path) {
+        Ok(original) => (
+            to_string_preserving(&original, config)?,
+            Some(std::fs::metadata(path)?.permissions()),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (to_string(config)?, None),
+        Err(e) => return Err(e.into()),
+    };
+    write_atomically(path, xml.as_bytes(), permissions)?;
+    Ok(())
+}
+
+/// Writes `contents` to a temporary file next to `path`, which then
+/// replaces `path`. The temporary file is removed if anything fails.
+fn write_atomically(
+    path: &Path,
+    contents: &[u8],
+    permissions: Option<std::fs::Permissions>,
+) -> std::io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&temp)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()?;
+        std::fs::rename(&temp, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result?;
+
+    // The rename itself is only durable once the directory is synced
+    #[cfg(unix)]
+    {
+        let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+        std::fs::File::open(dir.unwrap_or(Path::new(".")))?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn write_configuration(config: &Configuration, original: Option<&Node>) -> Result<String> {
+    let Value::Object(value) = serde_json::to_value(config)? else {
+        unreachable!("configuration is a struct");
+    };
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    write_element(
+        &mut writer,
+        "configuration",
+        &value,
+        &CONFIGURATION,
+        original,
+    )?;
+
+    let mut xml = String::from_utf8(writer.into_inner()).expect("writer only writes strings");
+    xml.push('\n');
+    Ok(xml)
+}
+
+/// Locates the `config.xml` of the local Syncthing instance.
+///
+/// Like Syncthing itself, this only looks in `$STHOMEDIR` or `$STCONFDIR`
+/// if one of them is set. Otherwise the platform's default location is
+/// used. On Linux, an existing configuration in `$XDG_CONFIG_HOME/syncthing`
+/// or `~/.config/syncthing` takes precedence over `$XDG_STATE_HOME/syncthing`
+/// (or `~/.local/state/syncthing` if unset).
+/// Returns `None` if none of the directories contains a `config.xml`.
+#[must_use]
+pub fn locate() -> Option<PathBuf> {
+    find_config_file(home_dir_candidates(|name| std::env::var_os(name)))
+}
+
+fn find_config_file(dirs: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    dirs.into_iter()
+        .map(|dir| dir.join("config.xml"))
+        .find(|path| path.is_file())
+}
+
+/// Directories in which Syncthing keeps its configuration, in the order
+/// in which they are searched. `env` looks up environment variables.
+fn home_dir_candidates(env: impl Fn(&str) -> Option<OsString>) -> Vec<PathBuf> {
+    let var = |name| env(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+    if let Some(dir) = var("STHOMEDIR").or_else(|| var("STCONFDIR")) {
+        return vec![dir];
+    }
+
+    let home = var("HOME");
+    let mut dirs = Vec::new();
+    if cfg!(windows) {
+        dirs.extend(var("LOCALAPPDATA").map(|dir| dir.join("Syncthing")));
+    } else if cfg!(target_os = "macos") {
+        dirs.extend(
+            home.as_ref()
+                .map(|home| home.join("Library/Application Support/Syncthing")),
+        );
+    } else {
+        // The default before 1.27, still used if it contains a configuration
+        let config = var("XDG_CONFIG_HOME").map(|dir| dir.join("syncthing"));
+        let legacy = home.as_ref().map(|h| h.join(".config/syncthing"));
+        let state = var("XDG_STATE_HOME").or_else(|| home.as_ref().map(|h| h.join(".local/state")));
+        for dir in [config, legacy, state.map(|dir| dir.join("syncthing"))]
+            .into_iter()
+            .flatten()
+        {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    dirs
+}
+
+/// A parsed XML element.
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+    text: String,
+}
+
+impl Node {
+    fn from_start(start: &BytesStart) -> Result<Self> {
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute.map_err(quick_xml::Error::from)?;
+            attributes.push((
+                attribute.key.as_ref().to_string(),
+                attribute
+                    .normalized_value(XmlVersion::Implicit1_0)?
+                    .into_owned(),
+            ));
+        }
+        Ok(Self {
+            name: start.name().as_ref().to_string(),
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    /// Parses `xml` and returns its root element.
+    fn parse(xml: &str) -> Result<Self> {
+        let mut reader = Reader::from_str(xml);
+        // The stack always contains a virtual document node
+        let mut stack = vec![Node::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => stack.push(Node::from_start(&start)?),
+                Event::Empty(start) => {
+                    let node = Node::from_start(&start)?;
+                    stack.last_mut().expect("document node").children.push(node);
+                }
+                Event::End(_) => {
+                    let node = stack.pop().expect("reader checks matching tags");
+                    stack.last_mut().expect("document node").children.push(node);
+                }
+                Event::Text(text) => push_text(&mut stack, &text.xml10_content()),
+                Event::CData(data) => push_text(&mut stack, &data.xml10_content()),
+                Event::GeneralRef(reference) => {
+                    let reference = format!("&{};", reference.xml10_content());
+                    let text = unescape(&reference).map_err(quick_xml::Error::from)?;
+                    push_text(&mut stack, &text);
+                }
+                Event::Eof => break,
+                Event::Decl(_) | Event::PI(_) | Event::Comment(_) | Event::DocType(_) => (),
+            }
+        }
+
+        let document = stack.pop().expect("document node");
+        if !stack.is_empty() {
+            return Err(Error::InvalidConfigXmlError("unclosed element".to_string()));
+        }
+        document
+            .children
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::InvalidConfigXmlError("no root element".to_string()))
+    }
+
+    /// Parses `xml` and checks that its root is a `<configuration>`.
+    fn parse_configuration(xml: &str) -> Result<Self> {
+        let root = Self::parse(xml)?;
+        if root.name != "configuration" {
+            return Err(Error::InvalidConfigXmlError(format!(
+                "expected <configuration>, found <{}>",
+                root.name
+            )));
+        }
+        Ok(root)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// Finds the original of the `index`th `<xml>` item written from
+    /// `object`. Items with an ID are matched by it, others by position.
+    fn item(
+        &self,
+        xml: &str,
+        layout: &Element,
+        object: &Map<String, Value>,
+        index: usize,
+    ) -> Option<&Node> {
+        let mut items = self.children.iter().filter(|child| child.name == xml);
+        match layout
+            .attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == "id")
+        {
+            Some((_, json)) => {
+                let id = to_text(object.get(*json)?);
+                items.find(|item| item.attribute("id") == Some(&id))
+            }
+            None => items.nth(index),
+        }
+    }
+
+    /// Translates this node into its JSON representation. All values are
+    /// strings, their actual type is only known when deserializing.
+    fn to_json(&self, element: &Element) -> Value {
+        let mut object = Map::new();
+
+        for (xml, json) in element.attributes {
+            if let Some(value) = self.attribute(xml) {
+                object.insert((*json).to_string(), value.into());
+            }
+        }
+        if let Some(json) = element.text {
+            object.insert(json.to_string(), self.text.clone().into());
+        }
+
+        // Lists and nested elements are also present if there are no items
+        for (_, json, kind) in element.children {
+            let empty = match kind {
+                Kind::Text => continue,
+                Kind::TextList | Kind::List(_) => Value::Array(Vec::new()),
+                Kind::Map { .. } => Value::Object(Map::new()),
+                Kind::Object(child) => Node::default().to_json(child),
+            };
+            object.insert((*json).to_string(), empty);
+        }
+
+        for child in &self.children {
+            match element.child_by_xml(&child.name) {
+                None => {
+                    object.insert(child.name.clone(), child.text.clone().into());
+                }
+                Some((json, Kind::Text)) => {
+                    object.insert(json.to_string(), child.text.clone().into());
+                }
+                Some((json, Kind::Object(layout))) => {
+                    object.insert(json.to_string(), child.to_json(layout));
+                }
+                Some((json, kind)) => {
+                    let item = match kind {
+                        Kind::List(layout) => child.to_json(layout),
+                        Kind::Map { key, value } => {
+                            let entry = object[json].as_object_mut().expect("inserted as map");
+                            entry.insert(
+                                child.attribute(key).unwrap_or_default().to_string(),
+                                child.attribute(value).unwrap_or_default().into(),
+                            );
+                            continue;
+                        }
+                        _ => child.text.clone().into(),
+                    };
+                    object[json]
+                        .as_array_mut()
+                        .expect("inserted as list")
+                        .push(item);
+                }
+            }
+        }
+
+        for (json, default) in element.defaults {
+            object.entry(*json).or_insert_with(|| (*default).into());
+        }
+
+        Value::Object(object)
+    }
+}
+
+fn push_text(stack: &mut [Node], text: &str) {
+    stack.last_mut().expect("document node").text.push_str(text);
+}
+
+/// Formats a JSON scalar as it appears in `config.xml`.
+fn to_text(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::String(s) => Cow::Borrowed(s),
+        Value::Null => Cow::Borrowed(""),
+        // Syncthing writes whole numbers without a fraction, e.g. `10` instead of `10.0`
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() && f.fract() == 0.0 => Cow::Owned(format!("{f:.0}")),
+            _ => Cow::Owned(n.to_string()),
+        },
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
+/// Writes `object` as an element with the given layout. Attributes and
+/// children of `original` which are not represented in `object` are kept.
+fn write_element(
+    writer: &mut Writer<Vec<u8>>,
+    name: &str,
+    object: &Map<String, Value>,
+    element: &Element,
+    original: Option<&Node>,
+) -> std::io::Result<()> {
+    let is_known = |xml: &str| {
+        element
+            .attributes
+            .iter()
+            .any(|(attribute, _)| *attribute == xml)
+            || element.child_by_xml(xml).is_some()
+            || object.contains_key(xml)
+    };
+    // Fields Syncthing omits if they are empty stay omitted
+    let is_omitted = |json: &str, value: &Value, present: bool| {
+        original.is_some()
+            && !present
+            && element
+                .defaults
+                .iter()
+                .any(|(field, default)| *field == json && to_text(value) == *default)
+    };
+    let mut attributes: Vec<(&str, Cow<str>)> = element
+        .attributes
+        .iter()
+        .filter_map(|(xml, json)| {
+            let value = object.get(*json)?;
+            let present = original.is_some_and(|node| node.attribute(xml).is_some());
+            (!is_omitted(json, value, present)).then(|| (*xml, to_text(value)))
+        })
+        .collect();
+    let unknown_attributes = original
+        .into_iter()
+        .flat_map(|node| &node.attributes)
+        .filter(|(xml, _)| !is_known(xml));
+    attributes.extend(unknown_attributes.map(|(xml, value)| (xml.as_str(), Cow::from(value))));
+    let start = writer
+        .create_element(name)
+        .with_attributes(attributes.iter().map(|(key, value)| (*key, value.as_ref())));
+
+    if let Some(json) = element.text {
+        let text = object.get(json).map(to_text).unwrap_or_default();
+        start.write_text_content(BytesText::new(&text))?;
+        return Ok(());
+    }
+
+    // Known children are written in the order of the layout, all others afterwards
+    let known = element
+        .children
+        .iter()
+        .filter_map(|(_, json, _)| object.get_key_value(*json));
+    let children = known.chain(object.iter().filter(|(json, _)| {
+        element.child_by_json(json).is_none()
+            && !element
+                .attributes
+                .iter()
+                .any(|(_, attribute)| attribute == json)
+    }));
+    start.write_inner_content(|writer| {
+        for (json, value) in children {
+            let xml = element
+                .child_by_json(json)
+                .map_or(json.as_str(), |(xml, _)| xml);
+            if is_omitted(
+                json,
+                value,
+                original.is_some_and(|node| node.child(xml).is_some()),
+            ) {
+                continue;
+            }
+            match (element.child_by_json(json), value) {
+                (Some((xml, Kind::Object(layout))), Value::Object(object)) => {
+                    let original = original.and_then(|node| node.child(xml));
+                    write_element(writer, xml, object, layout, original)?;
+                }
+                (Some((xml, Kind::List(layout))), Value::Array(items)) => {
+                    for (index, item) in items.iter().enumerate() {
+                        if let Value::Object(object) = item {
+                            let original =
+                                original.and_then(|node| node.item(xml, layout, object, index));
+                            write_element(writer, xml, object, layout, original)?;
+                        }
+                    }
+                }
+                (Some((xml, Kind::Map { key, value })), Value::Object(entries)) => {
+                    for (k, v) in entries {
+                        writer
+                            .create_element(xml)
+                            .with_attributes([(*key, k.as_str()), (*value, &to_text(v))])
+                            .write_empty()?;
+                    }
+                }
+                (child, Value::Array(items)) => {
+                    let xml = child.map_or(json.as_str(), |(xml, _)| xml);
+                    for item in items {
+                        writer
+                            .create_element(xml)
+                            .write_text_content(BytesText::new(&to_text(item)))?;
+                    }
+                }
+                (child, value) => {
+                    let xml = child.map_or(json.as_str(), |(xml, _)| xml);
+                    writer
+                        .create_element(xml)
+                        .write_text_content(BytesText::new(&to_text(value)))?;
+                }
+            }
+        }
+        let unknown_children = original
+            .into_iter()
+            .flat_map(|node| &node.children)
+            .filter(|child| !is_known(&child.name));
+        for child in unknown_children {
+            write_node(writer, child)?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Writes `node` unchanged, except for whitespace.
+fn write_node(writer: &mut Writer<Vec<u8>>, node: &Node) -> std::io::Result<()> {
+    let start = writer.create_element(&node.name).with_attributes(
+        node.attributes
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
+    if node.children.is_empty() {
+        start.write_text_content(BytesText::new(&node.text))?;
+    } else {
+        start.write_inner_content(|writer| {
+            node.children
+                .iter()
+                .try_for_each(|child| write_node(writer, child))
+        })?;
+    }
+    Ok(())
+}
+
+/// Deserializes the JSON representation of an XML document, in which all
+/// values are strings. The target type decides how a string is interpreted,
+/// e.g. `"true"` becomes a `bool` and `"10"` a number.
+struct Text(Value);
+
+impl<'de> IntoDeserializer<'de, de::value::Error> for Text {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> std::result::Result<V::Value, Self::Error> {
+                match self.0 {
+                    Value::String(s) => match s.parse() {
+                        Ok(value) => visitor.$visit(value),
+                        Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&s), &visitor)),
+                    },
+                    other => Text(other).deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Text {
+    type Error = de::value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::Number(n) => visitor.visit_string(n.to_string()),
+            Value::String(s) => visitor.visit_string(s),
+            Value::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter().map(Text));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(object) => {
+                let mut map = MapDeserializer::new(object.into_iter().map(|(k, v)| (k, Text(v))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> std::result::Result<V::Value, Self::Error> {
+        match self.0 {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            other => Text(other).deserialize_any(visitor),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct newtype_struct seq
+        tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_path(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name)
+    }
+
+    /// The XML and JSON fixtures describe the same instance.
+    #[test]
+    fn test_matches_json() {
+        let from_xml = from_file(data_path("config.xml")).unwrap();
+        let from_json: Configuration =
+            serde_json::from_str(&std::fs::read_to_string(data_path("config.json")).unwrap())
+                .unwrap();
+
+        assert_eq!(from_xml, from_json);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut config = from_file(data_path("config.xml")).unwrap();
+        // Also cover the optional layouts
+        config.folders[0]
+            .versioning
+            .params
+            .insert("keep".to_string(), "5".to_string());
+        config.folders[0]
+            .xattr_filter
+            .entries
+            .push(crate::types::config::XattrFilterEntry {
+                r#match: "user.*".to_string(),
+                permit: true,
+            });
+        config.devices[0].addresses = vec![
+            "tcp://192.0.2.1:22000".to_string(),
+            "quic://192.0.2.1:22000".to_string(),
+        ];
+        config.gui.password = "<&\"secret\">".to_string();
+
+        let xml = to_string(&config).unwrap();
+        assert!(xml.starts_with("<configuration version=\"37\">"));
+        assert!(xml.contains("<param key=\"keep\" val=\"5\"/>"));
+        assert!(xml.contains("fsWatcherDelayS=\"10\""));
+        assert!(xml.contains("<address>quic://192.0.2.1:22000</address>"));
+
+        assert_eq!(from_str(&xml).unwrap(), config);
+    }
+
+    /// Paths of all elements below `node`, with the names of their attributes.
+    fn element_set(node: &Node, path: &str, set: &mut Vec<String>) {
+        let path = format!("{path}/{}", node.name);
+        let mut attributes: Vec<_> = node
+            .attributes
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect();
+        attributes.sort_unstable();
+        set.push(format!("{path}{attributes:?}"));
+        for child in &node.children {
+            element_set(child, &path, set);
+        }
+    }
+
+    fn element_set_of(xml: &str) -> Vec<String> {
+        let mut set = Vec::new();
+        element_set(&Node::parse(xml).unwrap(), "", &mut set);
+        set.sort_unstable();
+        set
+    }
+
+    #[test]
+    fn test_to_file_preserves_elements() {
+        let original = std::fs::read_to_string(data_path("config.xml"))
+            .unwrap()
+            .replace(
+                "<folder id=\"default\"",
+                "<folder future=\"1\" id=\"default\"",
+            )
+            .replace(
+                "<options>",
+                "<options>\n<futureOption enabled=\"true\"><value>a &amp; b</value></futureOption>",
+            );
+        let path = std::env::temp_dir().join(format!("syncthing-rs-{}.xml", std::process::id()));
+        std::fs::write(&path, &original).unwrap();
+
+        let mut config = from_str(&original).unwrap();
+        config.options.relays_enabled = false;
+        to_file(&path, &config).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(element_set_of(&written), element_set_of(&original));
+        assert_eq!(from_str(&written).unwrap(), config);
+        assert!(written.contains("<value>a &amp; b</value>"));
+        assert!(written.contains("<folder id=\"default\""));
+        assert!(written.contains(" future=\"1\""));
+        // Without an existing file, nothing is preserved
+        assert!(!to_string(&config).unwrap().contains("futureOption"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_to_file_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let config = from_file(data_path("config.xml")).unwrap();
+        let dir = std::env::temp_dir().join(format!("syncthing-rs-perm-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.xml");
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        // A new file is only readable by its owner
+        to_file(&path, &config).unwrap();
+        let new_mode = mode(&path);
+        // An existing file keeps its permissions
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        to_file(&path, &config).unwrap();
+        let existing_mode = mode(&path);
+        let entries = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(new_mode, 0o600);
+        assert_eq!(existing_mode, 0o640);
+        // No temporary file is left behind
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn test_omitted_fields() {
+        let xml = std::fs::read_to_string(data_path("config.xml"))
+            .unwrap()
+            .replace("<apikey>WW3qcEcsmKuf9ZMJ3ymzF7orm4yVHQQj</apikey>", "")
+            .replace("<authMode>static</authMode>", "");
+        let config = from_str(&xml).unwrap();
+
+        assert_eq!(config.gui.api_key, "");
+        assert_eq!(
+            config.gui.auth_mode,
+            crate::types::config::AuthMode::StaticAuth
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            from_str("<configuration><folder></configuration>"),
+            Err(Error::XmlError(_))
+        ));
+        assert!(matches!(
+            from_str("<config version=\"37\"/>"),
+            Err(Error::InvalidConfigXmlError(_))
+        ));
+        assert!(matches!(
+            from_str("<configuration version=\"foo\"/>"),
+            Err(Error::InvalidConfigXmlError(_))
+        ));
+    }
+
+    #[test]
+    fn test_gui_only() {
+        let gui = gui_from_str(
+            r#"<configuration version="99">
+                <unknown/>
+                <gui enabled="true" tls="true" debugging="false" sendBasicAuthPrompt="false">
+                    <address>127.0.0.1:8384</address>
+                    <apikey>secret</apikey>
+                    <theme>dark</theme>
+                </gui>
+            </configuration>"#,
+        )
+        .unwrap();
+        assert!(gui.use_tls);
+        assert_eq!(gui.address, "127.0.0.1:8384");
+        assert_eq!(gui.api_key, "secret");
+
+        assert!(matches!(
+            gui_from_str("<configuration/>"),
+            Err(Error::InvalidConfigXmlError(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_home_dir_candidates() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+
+        assert_eq!(
+            home_dir_candidates(env(&[("HOME", "/home/user")])),
+            vec![
+                PathBuf::from("/home/user/.config/syncthing"),
+                PathBuf::from("/home/user/.local/state/syncthing"),
+            ]
+        );
+        assert_eq!(
+            home_dir_candidates(env(&[
+                ("HOME", "/home/user"),
+                ("XDG_STATE_HOME", "/state"),
+                ("XDG_CONFIG_HOME", "/config"),
+            ])),
+            vec![
+                PathBuf::from("/config/syncthing"),
+                PathBuf::from("/home/user/.config/syncthing"),
+                PathBuf::from("/state/syncthing"),
+            ]
+        );
+        assert_eq!(
+            home_dir_candidates(env(&[
+                ("HOME", "/home/user"),
+                ("STHOMEDIR", "/srv/syncthing"),
+                ("STCONFDIR", "/etc/syncthing"),
+            ])),
+            vec![PathBuf::from("/srv/syncthing")]
+        );
+        assert_eq!(
+            home_dir_candidates(env(&[
+                ("HOME", "/home/user"),
+                ("STCONFDIR", "/etc/syncthing")
+            ])),
+            vec![PathBuf::from("/etc/syncthing")]
+        );
+    }
+
+    /// Both the legacy and the current default directory contain a configuration.
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_locate_both_exist() {
+        let home = std::env::temp_dir().join(format!("syncthing-rs-home-{}", std::process::id()));
+        for dir in [".config/syncthing", ".local/state/syncthing", "empty"] {
+            std::fs::create_dir_all(home.join(dir)).unwrap();
+        }
+        for dir in [".config/syncthing", ".local/state/syncthing"] {
+            std::fs::write(home.join(dir).join("config.xml"), "").unwrap();
+        }
+        let home_var = home.clone().into_os_string();
+        let empty_var = home.join("empty").into_os_string();
+        let locate_with = |sthomedir: bool| {
+            find_config_file(home_dir_candidates(|name| match name {
+                "HOME" => Some(home_var.clone()),
+                "STHOMEDIR" if sthomedir => Some(empty_var.clone()),
+                _ => None,
+            }))
+        };
+
+        let found = locate_with(false);
+        // Set without a configuration, the defaults are not searched
+        let not_found = locate_with(true);
+        std::fs::remove_file(home.join(".config/syncthing/config.xml")).unwrap();
+        let state = locate_with(false);
+        std::fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(found, Some(home.join(".config/syncthing/config.xml")));
+        assert_eq!(not_found, None);
+        assert_eq!(state, Some(home.join(".local/state/syncthing/config.xml")));
+    }
+
+    #[test]
+    fn test_find_config_file() {
+        let data = data_path("");
+        assert_eq!(
+            find_config_file([data.join("missing"), data.clone()]),
+            Some(data.join("config.xml"))
+        );
+        assert_eq!(find_config_file([data.join("missing")]), None);
+    }
+}
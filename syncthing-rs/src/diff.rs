@@ -0,0 +1,364 @@
+//! Structural diffing of two [`Configuration`] snapshots.
+//!
+//! Folders and devices are matched by their ID, so reordering them is not
+//! considered a change. Inside a section, every changed field is reported
+//! with its JSON name, nested fields are joined with a `.`. The resulting
+//! change list can be serialized, e.g. to store it in an audit log.
+//!
+//! ```
+//! use syncthing_rs::{diff::Change, types::config::Configuration};
+//!
+//! # let json = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/config.json"));
+//! let old: Configuration = serde_json::from_str(json).unwrap();
+//! let mut new = old.clone();
+//! new.gui.theme = "dark".to_string();
+//!
+//! let changes = syncthing_rs::diff::diff(&old, &new);
+//! assert!(matches!(
+//!     &changes[..],
+//!     [Change::GuiChanged { changes }] if changes[0].field == "theme"
+//! ));
+//! ```
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::types::{
+    config::{Configuration, DeviceConfiguration, FolderConfiguration},
+    device_id::DeviceId,
+};
+
+/// A single changed field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    /// JSON name of the field, nested fields are separated by a `.`,
+    /// e.g. `versioning.params.keep`.
+    pub field: String,
+    /// Previous value, `null` if the field did not exist.
+    pub old: Value,
+    /// New value, `null` if the field was removed.
+    pub new: Value,
+}
+
+/// A change between two configurations.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "change", rename_all = "camelCase")]
+pub enum Change {
+    #[serde(rename_all = "camelCase")]
+    FolderAdded {
+        folder: Box<FolderConfiguration>,
+    },
+    #[serde(rename_all = "camelCase")]
+    FolderRemoved {
+        id: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    FolderChanged {
+        id: String,
+        changes: Vec<FieldChange>,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeviceAdded {
+        device: Box<DeviceConfiguration>,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeviceRemoved {
+        #[serde(rename = "deviceID")]
+        device_id: DeviceId,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeviceChanged {
+        #[serde(rename = "deviceID")]
+        device_id: DeviceId,
+        changes: Vec<FieldChange>,
+    },
+    GuiChanged {
+        changes: Vec<FieldChange>,
+    },
+    LdapChanged {
+        changes: Vec<FieldChange>,
+    },
+    OptionsChanged {
+        changes: Vec<FieldChange>,
+    },
+    DefaultsChanged {
+        changes: Vec<FieldChange>,
+    },
+    /// Changes to top-level fields such as `version` or `remoteIgnoredDevices`.
+    OtherChanged {
+        changes: Vec<FieldChange>,
+    },
+}
+
+/// Computes all changes from `old` to `new`.
+///
+/// Removed and changed folders and devices are reported in the order of
+/// `old`, added ones in the order of `new`.
+#[must_use]
+pub fn diff(old: &Configuration, new: &Configuration) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    for folder in &old.folders {
+        match new.folders.iter().find(|f| f.id == folder.id) {
+            None => changes.push(Change::FolderRemoved {
+                id: folder.id.clone(),
+            }),
+            Some(new_folder) => {
+                let field_changes = diff_fields(folder, new_folder);
+                if !field_changes.is_empty() {
+                    changes.push(Change::FolderChanged {
+                        id: folder.id.clone(),
+                        changes: field_changes,
+                    });
+                }
+            }
+        }
+    }
+    for folder in &new.folders {
+        if !old.folders.iter().any(|f| f.id == folder.id) {
+            changes.push(Change::FolderAdded {
+                folder: Box::new(folder.clone()),
+            });
+        }
+    }
+
+    for device in &old.devices {
+        match new.devices.iter().find(|d| d.device_id == device.device_id) {
+            None => changes.push(Change::DeviceRemoved {
+                device_id: device.device_id,
+            }),
+            Some(new_device) => {
+                let field_changes = diff_fields(device, new_device);
+                if !field_changes.is_empty() {
+                    changes.push(Change::DeviceChanged {
+                        device_id: device.device_id,
+                        changes: field_changes,
+                    });
+                }
+            }
+        }
+    }
+    for device in &new.devices {
+        if !old.devices.iter().any(|d| d.device_id == device.device_id) {
+            changes.push(Change::DeviceAdded {
+                device: Box::new(device.clone()),
+            });
+        }
+    }
+
+    let gui = diff_fields(&old.gui, &new.gui);
+    if !gui.is_empty() {
+        changes.push(Change::GuiChanged { changes: gui });
+    }
+    let ldap = diff_fields(&old.ldap, &new.ldap);
+    if !ldap.is_empty() {
+        changes.push(Change::LdapChanged { changes: ldap });
+    }
+    let options = diff_fields(&old.options, &new.options);
+    if !options.is_empty() {
+        changes.push(Change::OptionsChanged { changes: options });
+    }
+    let defaults = diff_fields(&old.defaults, &new.defaults);
+    if !defaults.is_empty() {
+        changes.push(Change::DefaultsChanged { changes: defaults });
+    }
+
+    let mut other = Vec::new();
+    if old.version != new.version {
+        other.push(FieldChange {
+            field: "version".to_string(),
+            old: old.version.into(),
+            new: new.version.into(),
+        });
+    }
+    if old.remote_ignored_devices != new.remote_ignored_devices {
+        other.push(FieldChange {
+            field: "remoteIgnoredDevices".to_string(),
+            old: to_value(&old.remote_ignored_devices),
+            new: to_value(&new.remote_ignored_devices),
+        });
+    }
+    if !other.is_empty() {
+        changes.push(Change::OtherChanged { changes: other });
+    }
+
+    changes
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    // The configuration types only consist of string keyed maps and
+    // plain values, which can always be represented as JSON.
+    serde_json::to_value(value).expect("configuration is serializable")
+}
+
+/// Compares the JSON representation of `old` and `new` field by field.
+fn diff_fields<T: Serialize>(old: &T, new: &T) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    match (to_value(old), to_value(new)) {
+        (Value::Object(old), Value::Object(new)) => diff_objects("", &old, &new, &mut changes),
+        (old, new) if old != new => changes.push(FieldChange {
+            field: String::new(),
+            old,
+            new,
+        }),
+        _ => (),
+    }
+    changes
+}
+
+fn diff_objects(
+    prefix: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    let removed = old.keys().filter(|key| !new.contains_key(*key));
+    for key in new.keys().chain(removed) {
+        let field = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match (old.get(key), new.get(key)) {
+            (Some(Value::Object(old)), Some(Value::Object(new))) => {
+                diff_objects(&field, old, new, changes);
+            }
+            (old, new) if old != new => changes.push(FieldChange {
+                field,
+                old: old.cloned().unwrap_or_default(),
+                new: new.cloned().unwrap_or_default(),
+            }),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn config() -> Configuration {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_no_changes() {
+        let mut new = config();
+        new.folders.reverse();
+        assert!(diff(&config(), &new).is_empty());
+    }
+
+    #[test]
+    fn test_folders() {
+        let old = config();
+        let mut new = config();
+
+        new.folders[0].label = "Renamed".to_string();
+        new.folders[0]
+            .versioning
+            .params
+            .insert("keep".to_string(), "5".to_string());
+        let mut added = new.folders[0].clone();
+        added.id = "added".to_string();
+        new.folders.push(added);
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changes[0],
+            Change::FolderChanged {
+                id: "default".to_string(),
+                changes: vec![
+                    FieldChange {
+                        field: "label".to_string(),
+                        old: "Default Folder".into(),
+                        new: "Renamed".into(),
+                    },
+                    FieldChange {
+                        field: "versioning.params.keep".to_string(),
+                        old: Value::Null,
+                        new: "5".into(),
+                    },
+                ],
+            }
+        );
+        assert!(matches!(&changes[1], Change::FolderAdded { folder } if folder.id == "added"));
+
+        assert_eq!(
+            diff(&new, &old)[1],
+            Change::FolderRemoved {
+                id: "added".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_devices() {
+        let old = config();
+        let mut new = config();
+        new.devices[0].paused = true;
+
+        let device_id = old.devices[0].device_id;
+        assert_eq!(
+            diff(&old, &new),
+            vec![Change::DeviceChanged {
+                device_id,
+                changes: vec![FieldChange {
+                    field: "paused".to_string(),
+                    old: false.into(),
+                    new: true.into(),
+                }],
+            }]
+        );
+
+        new.devices.clear();
+        assert_eq!(diff(&old, &new), vec![Change::DeviceRemoved { device_id }]);
+    }
+
+    #[test]
+    fn test_sections() {
+        let old = config();
+        let mut new = config();
+        new.gui.theme = "dark".to_string();
+        new.ldap.address = "ldap.example.com:389".to_string();
+        new.defaults.ignores.lines.push("*.tmp".to_string());
+        new.version += 1;
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 4);
+        assert!(
+            matches!(&changes[0], Change::GuiChanged { changes } if changes[0].field == "theme")
+        );
+        assert!(matches!(&changes[1], Change::LdapChanged { .. }));
+        assert!(
+            matches!(&changes[2], Change::DefaultsChanged { changes } if changes[0].field == "ignores.lines")
+        );
+        assert!(
+            matches!(&changes[3], Change::OtherChanged { changes } if changes[0].field == "version")
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let old = config();
+        let mut new = config();
+        new.folders[0].paused = true;
+
+        let changes = diff(&old, &new);
+        let json = serde_json::to_value(&changes).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "change": "folderChanged",
+                "id": "default",
+                "changes": [{"field": "paused", "old": false, "new": true}]
+            }])
+        );
+        assert_eq!(
+            serde_json::from_value::<Vec<Change>>(json).unwrap(),
+            changes
+        );
+    }
+}
@@ -9,6 +9,34 @@ pub enum Error {
     #[error(transparent)]
     NetworkError(#[from] reqwest::Error),
 
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    XmlError(#[from] quick_xml::Error),
+
+    #[error("invalid config.xml: {0}")]
+    InvalidConfigXmlError(String),
+
+    #[error("could not find the config.xml of a local syncthing instance")]
+    ConfigNotFoundError,
+
+    #[error("unsupported GUI address: {0}")]
+    UnsupportedGuiAddressError(String),
+
+    #[error("authentication failed, check the username and password")]
+    AuthenticationError,
+
+    #[error("could not parse event {id}: {source}")]
+    InvalidEventError {
+        id: u64,
+        #[source]
+        source: serde_json::Error,
+    },
+
     #[error("failed to send event (no receivers)")]
     SendEventError,
 
@@ -29,6 +57,45 @@ pub enum Error {
 
     #[error("device does not exist")]
     UnknownDeviceError,
+
+    #[error("ID {found} in the patch does not match {expected}")]
+    MismatchedIdError { expected: String, found: String },
+
+    #[error("file does not exist in the index")]
+    UnknownFileError,
+
+    #[error("timed out waiting for syncthing")]
+    TimeoutError,
+
+    #[error("configuration was changed concurrently: expected {expected}, found {found}")]
+    ConfigurationConflictError {
+        expected: crate::types::config::ConfigRevision,
+        found: crate::types::config::ConfigRevision,
+    },
+
+    #[error("invalid syncthing version: {0}")]
+    InvalidVersionError(String),
+
+    #[error("requires syncthing {required} or newer, but {running} is running")]
+    UnsupportedVersionError {
+        required: Box<crate::types::system::SyncthingVersion>,
+        running: Box<crate::types::system::SyncthingVersion>,
+    },
+
+    #[error("action is not supported by folders of type {0:?}")]
+    UnsupportedFolderTypeError(crate::types::config::FolderType),
+
+    #[error("invalid device ID: {0}")]
+    InvalidDeviceIdError(String),
+
+    #[error("could not read certificate: {0}")]
+    InvalidCertificateError(String),
+
+    #[error("a pinned certificate cannot be combined with {0}")]
+    ConflictingTlsOptionsError(String),
+
+    #[error("invalid ignore pattern: {0}")]
+    InvalidIgnorePatternError(String),
 }
 
 impl From<tokio::sync::broadcast::error::SendError<crate::types::events::Event>> for Error {
@@ -0,0 +1,304 @@
+//! Resumable streams of [events](Event), see [`Client::events`] and
+//! [`Client::disk_events`].
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use futures::Stream;
+
+use crate::{
+    Client,
+    error::{Error, Result},
+    types::events::{Event, EventTypeKind},
+};
+
+/// Delay before retrying after the first transient error.
+const RETRY_MIN: Duration = Duration::from_secs(1);
+
+/// The delay is doubled after every failed attempt up to this maximum.
+const RETRY_MAX: Duration = Duration::from_secs(30);
+
+/// Parameters of a single long polling request.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EventQuery {
+    /// Use the `/events/disk` endpoint instead of `/events`.
+    pub(crate) disk: bool,
+    pub(crate) since: u64,
+    /// Only the newest events are returned, so this is only used to get
+    /// the latest ID.
+    pub(crate) limit: Option<u64>,
+    pub(crate) timeout: Option<Duration>,
+    /// Comma separated list of event types to subscribe to.
+    pub(crate) events: Option<String>,
+}
+
+/// A [`Stream`] of [events](Event), created by [`Client::events`].
+///
+/// The stream long polls the API and yields every event once, in the order
+/// of their IDs. Transient errors, e.g. while Syncthing restarts, are logged
+/// and the stream resumes after the last received event, waiting longer
+/// after every consecutive failure. Events which cannot be parsed are
+/// yielded as [`InvalidEventError`](Error::InvalidEventError), without
+/// affecting the following events. Any other error is yielded and ends
+/// the stream.
+///
+/// Event IDs start at `1` again when Syncthing restarts. This is detected
+/// by a change of its [`start_time`](crate::types::system::SystemStatus::start_time)
+/// after an error, or if the stream would otherwise wait for an ID which
+/// Syncthing has not reached yet, e.g. when resuming after an ID of a
+/// previous instance. This is checked when the stream starts and after an
+/// error, not after a long poll which timed out without events. In both
+/// cases the stream continues with the first event of the new instance.
+///
+/// To continue after the process was restarted, persist
+/// [`last_id`](EventStream::last_id) and pass it to
+/// [`since`](EventStream::since).
+#[must_use = "streams do nothing unless polled"]
+pub struct EventStream {
+    client: Client,
+    query: EventQuery,
+    last_id: Arc<AtomicU64>,
+    inner: Option<Pin<Box<dyn Stream<Item = Result<Event>> + Send>>>,
+}
+
+impl EventStream {
+    pub(crate) fn new(client: Client, disk: bool) -> Self {
+        Self {
+            client,
+            query: EventQuery {
+                disk,
+                ..Default::default()
+            },
+            last_id: Arc::new(AtomicU64::new(0)),
+            inner: None,
+        }
+    }
+
+    /// Only yield events with an ID greater than `id`.
+    ///
+    /// By default, all events still buffered by Syncthing are yielded.
+    /// This has no effect once the stream has been polled.
+    pub fn since(mut self, id: u64) -> Self {
+        self.query.since = id;
+        self.last_id.store(id, Ordering::Relaxed);
+        self
+    }
+
+    /// Only yield events of the given kinds, which are filtered by Syncthing.
+    /// [`EventTypeKind::Unknown`] does not match any event.
+    ///
+    /// Event IDs are counted separately for every set of kinds, so to resume
+    /// with [`since`](EventStream::since), the same kinds have to be used.
+    /// This has no effect once the stream has been polled.
+    ///
+    /// ```no_run
+    /// # fn example(client: syncthing_rs::Client) {
+    /// use syncthing_rs::types::events::EventTypeKind;
+    ///
+    /// let events = client
+    ///     .events()
+    ///     .kinds([EventTypeKind::FolderCompletion, EventTypeKind::StateChanged]);
+    /// # }
+    /// ```
+    pub fn kinds(mut self, kinds: impl IntoIterator<Item = EventTypeKind>) -> Self {
+        let kinds: Vec<_> = kinds.into_iter().map(|kind| kind.as_str()).collect();
+        self.query.events = Some(kinds.join(","));
+        self
+    }
+
+    /// How long Syncthing waits for new events before answering a request
+    /// without any, 60 seconds by default.
+    ///
+    /// This has no effect once the stream has been polled.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.query.timeout = Some(timeout);
+        self
+    }
+
+    /// Returns the ID of the last yielded event, or the ID passed to
+    /// [`since`](EventStream::since) if none was yielded yet.
+    #[must_use]
+    pub fn last_id(&self) -> u64 {
+        self.last_id.load(Ordering::Relaxed)
+    }
+}
+
+/// A [`Stream`] of disk [events](Event), created by [`Client::disk_events`].
+///
+/// This behaves like an [`EventStream`], except that the events cannot be
+/// filtered by kind, as Syncthing ignores the filter for disk events.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct DiskEventStream(EventStream);
+
+impl DiskEventStream {
+    pub(crate) fn new(client: Client) -> Self {
+        Self(EventStream::new(client, true))
+    }
+
+    /// Only yield events with an ID greater than `id`, see [`EventStream::since`].
+    pub fn since(self, id: u64) -> Self {
+        Self(self.0.since(id))
+    }
+
+    /// How long Syncthing waits for new events, see [`EventStream::timeout`].
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self(self.0.timeout(timeout))
+    }
+
+    /// Returns the ID of the last yielded event, see [`EventStream::last_id`].
+    #[must_use]
+    pub fn last_id(&self) -> u64 {
+        self.0.last_id()
+    }
+}
+
+impl Stream for DiskEventStream {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("query", &self.query)
+            .field("last_id", &self.last_id())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for EventStream {
+    type Item = Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let inner = this.inner.get_or_insert_with(|| {
+            let state = State {
+                client: this.client.clone(),
+                query: this.query.clone(),
+                last_id: this.last_id.clone(),
+                buffer: VecDeque::new(),
+                retry: RETRY_MIN,
+                start_time: None,
+                // Also detects a `since` of a previous instance
+                check_reset: true,
+            };
+            Box::pin(futures::stream::unfold(Some(state), |state| async move {
+                let mut state = state?;
+                match state.next().await {
+                    Err(e) if !matches!(e, Error::InvalidEventError { .. }) => Some((Err(e), None)),
+                    result => Some((result, Some(state))),
+                }
+            }))
+        });
+        inner.as_mut().poll_next(cx)
+    }
+}
+
+struct State {
+    client: Client,
+    /// `since` is the ID of the last received event.
+    query: EventQuery,
+    last_id: Arc<AtomicU64>,
+    /// Received, but not yet yielded events.
+    buffer: VecDeque<Result<Event>>,
+    retry: Duration,
+    /// When Syncthing was started, `None` until it was first requested.
+    start_time: Option<DateTime<Utc>>,
+    check_reset: bool,
+}
+
+impl State {
+    /// Returns the next event, only returns non-transient errors.
+    async fn next(&mut self) -> Result<Event> {
+        loop {
+            if let Some(event) = self.buffer.pop_front() {
+                if let Some(id) = event_id(&event) {
+                    self.last_id.store(id, Ordering::Relaxed);
+                }
+                return event;
+            }
+
+            let result = if self.check_reset {
+                self.check_reset().await
+            } else {
+                self.fetch().await
+            };
+            match result {
+                Ok(()) => self.retry = RETRY_MIN,
+                Err(e) if is_transient(&e) => {
+                    log::warn!(
+                        "failed to receive events, retrying in {:?}: {e}",
+                        self.retry
+                    );
+                    tokio::time::sleep(self.retry).await;
+                    self.retry = (self.retry * 2).min(RETRY_MAX);
+                    // Syncthing might have restarted in the meantime
+                    self.check_reset = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn fetch(&mut self) -> Result<()> {
+        let events = self.client.fetch_events(&self.query).await?;
+        if let Some(id) = events.iter().filter_map(event_id).max() {
+            self.query.since = self.query.since.max(id);
+        }
+        self.buffer.extend(events);
+        Ok(())
+    }
+
+    /// Checks whether Syncthing has restarted, when the stream starts and
+    /// after an error. The first check only remembers the start time.
+    async fn check_reset(&mut self) -> Result<()> {
+        let start_time = self.client.start_time().await?;
+        let before = self.start_time.replace(start_time);
+        if before.is_some_and(|before| before != start_time) {
+            log::warn!("syncthing has restarted since {before:?}");
+            self.query.since = 0;
+        } else if self.query.since > 0 {
+            let latest = self.client.latest_event_id(&self.query).await?;
+            if latest < self.query.since {
+                log::warn!(
+                    "event ID {latest} is lower than {}, syncthing has restarted",
+                    self.query.since
+                );
+                self.query.since = 0;
+            }
+        }
+        self.check_reset = false;
+        Ok(())
+    }
+}
+
+/// Returns the ID of a parsed or invalid event.
+pub(crate) fn event_id(event: &Result<Event>) -> Option<u64> {
+    match event {
+        Ok(event) => Some(event.id),
+        Err(Error::InvalidEventError { id, .. }) => Some(*id),
+        Err(_) => None,
+    }
+}
+
+/// Whether retrying a request might succeed.
+fn is_transient(error: &Error) -> bool {
+    match error {
+        Error::NetworkError(e) => {
+            !e.is_decode() && !e.is_builder() && e.status().is_none_or(|s| s.is_server_error())
+        }
+        _ => false,
+    }
+}
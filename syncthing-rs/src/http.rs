@@ -0,0 +1,250 @@
+//! HTTP transport of the [`Client`](crate::Client), which authenticates
+//! every request.
+//!
+//! With an API key, the key is simply sent as a default header. With a
+//! username and password, Syncthing additionally requires a CSRF token,
+//! which is handed out as a cookie by the GUI and has to be echoed in the
+//! `X-CSRF-Token-<id>` header. The session (or CSRF token) is obtained
+//! before the first request and renewed once a request is rejected.
+use std::sync::Arc;
+
+use reqwest::{
+    Method, Response, StatusCode,
+    header::{self, HeaderName, HeaderValue},
+};
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::error::{Error, Result};
+
+/// How to authenticate with a username and password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PasswordAuth {
+    /// Send the credentials with every request.
+    Basic,
+    /// Log in once and send the session cookie.
+    Session,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct HttpClient {
+    client: reqwest::Client,
+    session: Option<Arc<Session>>,
+}
+
+impl HttpClient {
+    /// Creates a client, which is already authenticated by its default headers.
+    pub(crate) fn new(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            session: None,
+        }
+    }
+
+    /// Creates a client, which authenticates with `user` and `password`
+    /// against the GUI of the REST API at `base_url`.
+    pub(crate) fn with_password(
+        client: reqwest::Client,
+        base_url: &str,
+        user: String,
+        password: String,
+        mode: PasswordAuth,
+    ) -> Self {
+        let gui_url = base_url.strip_suffix("/rest").unwrap_or(base_url);
+        Self {
+            client,
+            session: Some(Arc::new(Session {
+                gui_url: gui_url.to_string(),
+                user,
+                password,
+                mode,
+                state: RwLock::default(),
+            })),
+        }
+    }
+
+    pub(crate) fn get(&self, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        self.request(Method::GET, url)
+    }
+
+    pub(crate) fn post(&self, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        self.request(Method::POST, url)
+    }
+
+    pub(crate) fn put(&self, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        self.request(Method::PUT, url)
+    }
+
+    pub(crate) fn patch(&self, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        self.request(Method::PATCH, url)
+    }
+
+    pub(crate) fn delete(&self, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        self.request(Method::DELETE, url)
+    }
+
+    fn request(&self, method: Method, url: impl reqwest::IntoUrl) -> RequestBuilder<'_> {
+        RequestBuilder {
+            http: self,
+            request: self.client.request(method, url),
+        }
+    }
+}
+
+/// A request, which is authenticated when it is sent.
+#[must_use]
+pub(crate) struct RequestBuilder<'a> {
+    http: &'a HttpClient,
+    request: reqwest::RequestBuilder,
+}
+
+impl RequestBuilder<'_> {
+    pub(crate) fn json<T: Serialize + ?Sized>(mut self, json: &T) -> Self {
+        self.request = self.request.json(json);
+        self
+    }
+
+    /// Sends the request. If it is rejected because the session expired,
+    /// it is repeated once after authenticating again. If it is still
+    /// rejected, the credentials are wrong.
+    pub(crate) async fn send(self) -> Result<Response> {
+        let Some(session) = &self.http.session else {
+            return Ok(self.request.send().await?);
+        };
+        let client = &self.http.client;
+        let retry = self.request.try_clone();
+
+        let (generation, response) = session.send(client, self.request).await?;
+        match retry {
+            Some(retry) if is_rejected(&response) => {
+                log::debug!("request was rejected, authenticating again");
+                session.authenticate(client, generation).await?;
+                let response = session.send(client, retry).await?.1;
+                if is_rejected(&response) {
+                    return Err(Error::AuthenticationError);
+                }
+                Ok(response)
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
+fn is_rejected(response: &Response) -> bool {
+    matches!(
+        response.status(),
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+    )
+}
+
+#[derive(Debug)]
+struct Session {
+    /// URL of the GUI, i.e. without the `/rest` suffix.
+    gui_url: String,
+    user: String,
+    password: String,
+    mode: PasswordAuth,
+    state: RwLock<SessionState>,
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    /// Incremented on every authentication, `0` if not yet authenticated.
+    generation: u64,
+    csrf: Option<(HeaderName, HeaderValue)>,
+    cookie: Option<HeaderValue>,
+}
+
+impl Session {
+    /// Sends `request` with the current credentials and returns the
+    /// generation of the credentials which were used.
+    async fn send(
+        &self,
+        client: &reqwest::Client,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<(u64, Response)> {
+        if self.state.read().await.generation == 0 {
+            self.authenticate(client, 0).await?;
+        }
+
+        let state = self.state.read().await;
+        if let Some((name, value)) = &state.csrf {
+            request = request.header(name, value);
+        }
+        if let Some(cookie) = &state.cookie {
+            request = request.header(header::COOKIE, cookie);
+        }
+        if self.mode == PasswordAuth::Basic {
+            request = request.basic_auth(&self.user, Some(&self.password));
+        }
+        let generation = state.generation;
+        drop(state);
+
+        Ok((generation, request.send().await?))
+    }
+
+    /// Fetches a new CSRF token and, for sessions, logs in, unless this
+    /// was already done by another request since `generation` was used.
+    async fn authenticate(&self, client: &reqwest::Client, generation: u64) -> Result<()> {
+        let mut state = self.state.write().await;
+        if state.generation != generation {
+            return Ok(());
+        }
+
+        // Any page outside of the REST API sets the CSRF cookie
+        log::debug!("GET / (CSRF token)");
+        let response = client
+            .get(format!("{}/", self.gui_url))
+            .send()
+            .await?
+            .error_for_status()?;
+        state.csrf = cookies(&response).find_map(|(name, value)| {
+            let id = name.strip_prefix("CSRF-Token-")?;
+            let name = HeaderName::try_from(format!("X-CSRF-Token-{id}")).ok()?;
+            Some((name, HeaderValue::from_str(value).ok()?))
+        });
+
+        if self.mode == PasswordAuth::Session {
+            log::debug!("POST /rest/noauth/auth/password");
+            let mut request = client
+                .post(format!("{}/rest/noauth/auth/password", self.gui_url))
+                .json(&serde_json::json!({
+                    "username": self.user,
+                    "password": self.password,
+                    "stayLoggedIn": false,
+                }));
+            if let Some((name, value)) = &state.csrf {
+                request = request.header(name, value);
+            }
+            let response = request.send().await?;
+            if is_rejected(&response) {
+                return Err(Error::AuthenticationError);
+            }
+            let response = response.error_for_status()?;
+            let session = cookies(&response)
+                .find(|(name, _)| name.starts_with("sessionid-"))
+                .ok_or(Error::AuthenticationError)?;
+            state.cookie = Some(HeaderValue::from_str(&format!(
+                "{}={}",
+                session.0, session.1
+            ))?);
+        }
+
+        state.generation += 1;
+        Ok(())
+    }
+}
+
+/// Returns the name and value of all cookies set by `response`.
+fn cookies(response: &Response) -> impl Iterator<Item = (&str, &str)> {
+    response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .filter_map(|cookie| {
+            let pair = cookie.split(';').next()?;
+            let (name, value) = pair.split_once('=')?;
+            Some((name.trim(), value.trim()))
+        })
+}
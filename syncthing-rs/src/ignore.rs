@@ -0,0 +1,607 @@
+//! Parser and matcher for `.stignore` files, as defined [here](https://docs.syncthing.net/users/ignoring.html)
+//!
+//! This allows to check locally whether a path would be ignored by
+//! Syncthing, before pushing the patterns through
+//! [`set_ignores`](crate::Client::set_ignores).
+//!
+//! ```
+//! use syncthing_rs::ignore::IgnoreMatcher;
+//!
+//! let matcher = IgnoreMatcher::new(&["!/keep.log", "*.log", "(?i)/build/"]).unwrap();
+//! assert!(matcher.is_ignored("logs/error.log"));
+//! assert!(!matcher.is_ignored("keep.log"));
+//! assert!(matcher.is_ignored("BUILD/output.o"));
+//! ```
+use std::path::{Component, Path, PathBuf};
+
+use crate::error::{Error, Result};
+
+/// Files and directories Syncthing always ignores.
+const INTERNAL: [&str; 3] = [".stfolder", ".stignore", ".stversions"];
+
+const INCLUDE: &str = "#include";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`, matching a single character except `/`
+    AnyChar,
+    /// `*`, matching any number of characters except `/`
+    Star,
+    /// `**`, matching any number of characters
+    SuperStar,
+    /// `[...]`, matching a single character except `/`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches_char(&self, c: char) -> bool {
+        match self {
+            Token::Char(expected) => *expected == c,
+            Token::AnyChar => c != '/',
+            Token::Class { negated, ranges } => {
+                c != '/' && ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&c)) != *negated
+            }
+            Token::Star | Token::SuperStar => unreachable!("handled by glob_matches"),
+        }
+    }
+}
+
+/// Parses a glob with alternatives (`{a,b}`) into one token sequence per alternative.
+fn parse_glob(chars: &[char], pos: &mut usize, nested: bool) -> Result<Vec<Vec<Token>>> {
+    let invalid = |reason: &str| Error::InvalidIgnorePatternError(reason.to_string());
+    let mut results = vec![Vec::new()];
+    while let Some(&c) = chars.get(*pos) {
+        let token = match c {
+            '}' | ',' if nested => return Ok(results),
+            '{' => {
+                *pos += 1;
+                let mut alternatives = Vec::new();
+                loop {
+                    alternatives.extend(parse_glob(chars, pos, true)?);
+                    match chars.get(*pos) {
+                        Some(',') => *pos += 1,
+                        Some('}') => break,
+                        _ => return Err(invalid("unclosed '{'")),
+                    }
+                }
+                results = results
+                    .iter()
+                    .flat_map(|r| alternatives.iter().map(move |a| [r.as_slice(), a].concat()))
+                    .collect();
+                *pos += 1;
+                continue;
+            }
+            '*' if chars.get(*pos + 1) == Some(&'*') => {
+                *pos += 1;
+                Token::SuperStar
+            }
+            '*' => Token::Star,
+            '?' => Token::AnyChar,
+            '[' => {
+                *pos += 1;
+                let negated = chars.get(*pos) == Some(&'!');
+                if negated {
+                    *pos += 1;
+                }
+                let mut ranges = Vec::new();
+                loop {
+                    match chars.get(*pos) {
+                        Some(']') => break,
+                        Some(&lo) => {
+                            if chars.get(*pos + 1) == Some(&'-')
+                                && let Some(&hi) = chars.get(*pos + 2)
+                                && hi != ']'
+                            {
+                                ranges.push((lo, hi));
+                                *pos += 3;
+                            } else {
+                                ranges.push((lo, lo));
+                                *pos += 1;
+                            }
+                        }
+                        None => return Err(invalid("unclosed '['")),
+                    }
+                }
+                Token::Class { negated, ranges }
+            }
+            '\\' => {
+                *pos += 1;
+                Token::Char(*chars.get(*pos).ok_or_else(|| invalid("trailing '\\'"))?)
+            }
+            c => Token::Char(c),
+        };
+        for result in &mut results {
+            result.push(token.clone());
+        }
+        *pos += 1;
+    }
+    Ok(results)
+}
+
+/// Matches `text` against `tokens` in `O(tokens * text)` time, by computing
+/// for every token from the end whether the remaining tokens match every
+/// suffix of `text`.
+fn glob_matches(tokens: &[Token], text: &[char]) -> bool {
+    // `next[j]`: the tokens after the current one match `text[j..]`
+    let mut next: Vec<bool> = (0..=text.len()).map(|j| j == text.len()).collect();
+    let mut current = vec![false; text.len() + 1];
+    for token in tokens.iter().rev() {
+        for j in (0..=text.len()).rev() {
+            current[j] = match token {
+                Token::SuperStar => next[j] || (j < text.len() && current[j + 1]),
+                Token::Star => next[j] || (j < text.len() && text[j] != '/' && current[j + 1]),
+                token => j < text.len() && token.matches_char(text[j]) && next[j + 1],
+            };
+        }
+        std::mem::swap(&mut next, &mut current);
+    }
+    next[0]
+}
+
+/// Removes `.` and `..` components from `path`, as far as possible
+/// without looking at the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Returns the path of an `#include` line, which may be empty.
+fn include_path(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix(INCLUDE)?;
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then(|| rest.trim())
+}
+
+/// A single line of an ignore file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IgnorePattern {
+    /// The pattern without any prefixes.
+    pub pattern: String,
+    /// `!` prefix: matching files are explicitly *not* ignored.
+    pub negated: bool,
+    /// `(?i)` prefix: the pattern is matched case-insensitively.
+    pub case_insensitive: bool,
+    /// `(?d)` prefix: matching files may be deleted if they prevent
+    /// a directory from being removed.
+    pub deletable: bool,
+    /// `/` prefix: the pattern only matches in the root of the folder.
+    pub anchored: bool,
+    globs: Vec<Vec<Token>>,
+}
+
+impl IgnorePattern {
+    /// Parses a single pattern line. Returns `None` for empty lines and `//` comments.
+    ///
+    /// # Errors
+    ///
+    /// This method fails for `#include` lines, which can only be resolved by an
+    /// [`IgnoreMatcher`], and for invalid patterns.
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            return Ok(None);
+        }
+        if include_path(line).is_some() {
+            return Err(Error::InvalidIgnorePatternError(format!(
+                "cannot resolve {line} without an ignore file"
+            )));
+        }
+
+        // Prefixes may be specified in any order, but only once
+        let mut rest = line;
+        let (mut negated, mut case_insensitive, mut deletable) = (false, false, false);
+        loop {
+            if !negated && let Some(stripped) = rest.strip_prefix('!') {
+                negated = true;
+                rest = stripped;
+            } else if !case_insensitive && let Some(stripped) = rest.strip_prefix("(?i)") {
+                case_insensitive = true;
+                rest = stripped;
+            } else if !deletable && let Some(stripped) = rest.strip_prefix("(?d)") {
+                deletable = true;
+                rest = stripped;
+            } else {
+                break;
+            }
+        }
+        if rest.is_empty() {
+            return Err(Error::InvalidIgnorePatternError(format!(
+                "missing pattern in {line}"
+            )));
+        }
+
+        let pattern = rest.to_string();
+        let rest = if case_insensitive {
+            rest.to_lowercase()
+        } else {
+            rest.to_string()
+        };
+        let anchored = rest.starts_with('/');
+
+        // Like Syncthing, a pattern matches the path itself and everything
+        // below it, unless it explicitly only matches the contents.
+        let variants = if rest.ends_with("/**") {
+            vec![rest]
+        } else if rest.ends_with('/') {
+            vec![format!("{rest}**")]
+        } else {
+            vec![rest.clone(), format!("{rest}/**")]
+        };
+
+        let mut globs = Vec::new();
+        for variant in variants {
+            let sources = match variant.strip_prefix('/') {
+                Some(rooted) => vec![rooted.to_string()],
+                None => vec![variant.clone(), format!("**/{variant}")],
+            };
+            for source in sources {
+                let chars: Vec<char> = source.chars().collect();
+                globs.extend(parse_glob(&chars, &mut 0, false)?);
+            }
+        }
+
+        Ok(Some(Self {
+            pattern,
+            negated,
+            case_insensitive,
+            deletable,
+            anchored,
+            globs,
+        }))
+    }
+
+    /// Returns `true` if the relative `path` matches this pattern,
+    /// regardless of whether it is negated.
+    #[must_use]
+    pub fn matches(&self, path: &str) -> bool {
+        let path = if self.case_insensitive {
+            path.to_lowercase()
+        } else {
+            path.to_string()
+        };
+        let chars: Vec<char> = path.chars().collect();
+        self.globs.iter().any(|glob| glob_matches(glob, &chars))
+    }
+}
+
+/// All patterns of an ignore file, with `#include` directives resolved.
+///
+/// Patterns are evaluated in order, the first matching pattern decides
+/// whether a path is ignored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IgnoreMatcher {
+    pub patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreMatcher {
+    /// Parses the lines of an ignore file, such as
+    /// [`FolderIgnores::ignore`](crate::types::db::FolderIgnores::ignore).
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a pattern is invalid or an `#include` is used.
+    /// Use [`with_includes`](IgnoreMatcher::with_includes) or
+    /// [`from_file`](IgnoreMatcher::from_file) to resolve those.
+    pub fn new(lines: &[impl AsRef<str>]) -> Result<Self> {
+        Self::with_includes(lines, |line| {
+            Err(Error::InvalidIgnorePatternError(format!(
+                "cannot resolve {line} without an ignore file"
+            )))
+        })
+    }
+
+    /// Parses the lines of an ignore file, calling `load` with the path of
+    /// every `#include` to get the lines of the included file. Like in
+    /// Syncthing, the path is relative to the directory of the file which
+    /// contains the `#include`, and passed to `load` relative to the
+    /// directory of the parsed file.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a pattern is invalid, `load` fails or
+    /// a file includes itself, directly or through other files.
+    pub fn with_includes(
+        lines: &[impl AsRef<str>],
+        mut load: impl FnMut(&str) -> Result<Vec<String>>,
+    ) -> Result<Self> {
+        let mut matcher = Self::default();
+        // Joined with `/` on all platforms, as in the ignore file itself
+        let mut load = |path: &Path| {
+            let components: Vec<_> = path
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect();
+            load(&components.join("/"))
+        };
+        matcher.parse_lines(lines, Path::new(""), &mut load, &mut Vec::new())?;
+        Ok(matcher)
+    }
+
+    /// Reads and parses an ignore file, such as `.stignore`. Included files
+    /// are resolved relative to the directory of the file including them.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if a file cannot be read, a pattern is invalid or
+    /// a file includes itself, directly or through other files.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = normalize(path.as_ref());
+        let mut read = |path: &Path| -> Result<Vec<String>> {
+            Ok(std::fs::read_to_string(path)?
+                .lines()
+                .map(str::to_string)
+                .collect())
+        };
+        let mut matcher = Self::default();
+        let lines = read(&path)?;
+        matcher.parse_lines(&lines, &path, &mut read, &mut vec![path.clone()])?;
+        Ok(matcher)
+    }
+
+    /// Parses the lines of `file`. `stack` contains the files which are
+    /// currently being parsed, to detect cycles.
+    fn parse_lines(
+        &mut self,
+        lines: &[impl AsRef<str>],
+        file: &Path,
+        load: &mut impl FnMut(&Path) -> Result<Vec<String>>,
+        stack: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        for line in lines {
+            let line = line.as_ref().trim();
+            if let Some(include) = include_path(line) {
+                if include.is_empty() {
+                    return Err(Error::InvalidIgnorePatternError(
+                        "#include without a file".to_string(),
+                    ));
+                }
+                let path = normalize(&file.parent().unwrap_or(Path::new("")).join(include));
+                if stack.contains(&path) {
+                    return Err(Error::InvalidIgnorePatternError(format!(
+                        "{} includes itself",
+                        path.display()
+                    )));
+                }
+                let included = load(&path)?;
+                stack.push(path.clone());
+                self.parse_lines(&included, &path, load, stack)?;
+                stack.pop();
+            } else if let Some(pattern) = IgnorePattern::parse(line)? {
+                self.patterns.push(pattern);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the first pattern matching the relative `path`, if any.
+    #[must_use]
+    pub fn matching_pattern(&self, path: &str) -> Option<&IgnorePattern> {
+        self.patterns.iter().find(|pattern| pattern.matches(path))
+    }
+
+    /// Returns `true` if Syncthing would ignore the relative `path`.
+    #[must_use]
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let path = path.trim_start_matches('/');
+        if INTERNAL
+            .iter()
+            .any(|internal| path == *internal || path.starts_with(&format!("{internal}/")))
+        {
+            return true;
+        }
+        self.matching_pattern(path)
+            .is_some_and(|pattern| !pattern.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prefixes() {
+        let pattern = IgnorePattern::parse("(?d)!(?i)/Foo").unwrap().unwrap();
+
+        assert_eq!(pattern.pattern, "/Foo");
+        assert!(pattern.negated);
+        assert!(pattern.case_insensitive);
+        assert!(pattern.deletable);
+        assert!(pattern.anchored);
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        assert_eq!(IgnorePattern::parse("// comment").unwrap(), None);
+        assert_eq!(IgnorePattern::parse("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(IgnorePattern::parse("!").is_err());
+        assert!(IgnorePattern::parse("foo[").is_err());
+        assert!(IgnorePattern::parse("{a,b").is_err());
+        assert!(IgnorePattern::parse("#include other").is_err());
+    }
+
+    #[test]
+    fn test_unanchored() {
+        let matcher = IgnoreMatcher::new(&["foo"]).unwrap();
+
+        assert!(matcher.is_ignored("foo"));
+        assert!(matcher.is_ignored("foo/bar"));
+        assert!(matcher.is_ignored("bar/foo"));
+        assert!(matcher.is_ignored("bar/foo/baz"));
+        assert!(!matcher.is_ignored("foobar"));
+    }
+
+    #[test]
+    fn test_anchored() {
+        let matcher = IgnoreMatcher::new(&["/foo"]).unwrap();
+
+        assert!(matcher.is_ignored("foo"));
+        assert!(matcher.is_ignored("foo/bar"));
+        assert!(!matcher.is_ignored("bar/foo"));
+    }
+
+    #[test]
+    fn test_directory_contents() {
+        let matcher = IgnoreMatcher::new(&["foo/"]).unwrap();
+
+        assert!(!matcher.is_ignored("foo"));
+        assert!(matcher.is_ignored("foo/bar"));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let matcher =
+            IgnoreMatcher::new(&["/a*c", "/x/**/z", "?.tmp", "[0-9]x", "*.{jpg,png}"]).unwrap();
+
+        assert!(matcher.is_ignored("abc"));
+        assert!(!matcher.is_ignored("ab/c"));
+        assert!(matcher.is_ignored("x/y/w/z"));
+        assert!(matcher.is_ignored("a.tmp"));
+        assert!(!matcher.is_ignored("ab.tmp"));
+        assert!(matcher.is_ignored("7x"));
+        assert!(!matcher.is_ignored("ax"));
+        assert!(matcher.is_ignored("photos/cat.png"));
+        assert!(!matcher.is_ignored("photos/cat.gif"));
+    }
+
+    /// Many wildcards must not cause exponential backtracking.
+    #[test]
+    fn test_many_wildcards() {
+        let matcher =
+            IgnoreMatcher::new(&["/**a**a**a**a**a**a**a**a**a**a**b", "*a*a*a*a*a*a*a*a*c"])
+                .unwrap();
+        let path = vec!["a"; 200].join("/") + &"a".repeat(200);
+
+        assert!(!matcher.is_ignored(&path));
+        assert!(matcher.is_ignored(&(path + "b")));
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let matcher = IgnoreMatcher::new(&["!important.log", "*.log"]).unwrap();
+
+        assert!(!matcher.is_ignored("important.log"));
+        assert!(matcher.is_ignored("other.log"));
+
+        let matcher = IgnoreMatcher::new(&["*.log", "!important.log"]).unwrap();
+        assert!(matcher.is_ignored("important.log"));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let matcher = IgnoreMatcher::new(&["(?i)readme"]).unwrap();
+
+        assert!(matcher.is_ignored("README"));
+        assert!(matcher.is_ignored("docs/ReadMe"));
+
+        let matcher = IgnoreMatcher::new(&["readme"]).unwrap();
+        assert!(!matcher.is_ignored("README"));
+    }
+
+    #[test]
+    fn test_escape() {
+        let matcher = IgnoreMatcher::new(&["/\\*"]).unwrap();
+
+        assert!(matcher.is_ignored("*"));
+        assert!(!matcher.is_ignored("a"));
+    }
+
+    #[test]
+    fn test_internal_files() {
+        let matcher = IgnoreMatcher::default();
+
+        assert!(matcher.is_ignored(".stfolder"));
+        assert!(matcher.is_ignored(".stversions/foo"));
+        assert!(!matcher.is_ignored("foo"));
+    }
+
+    #[test]
+    fn test_includes() {
+        let matcher = IgnoreMatcher::with_includes(&["#include more", "*.tmp"], |include| {
+            assert_eq!(include, "more");
+            Ok(vec!["!keep.tmp".to_string()])
+        })
+        .unwrap();
+
+        assert_eq!(matcher.patterns.len(), 2);
+        assert!(!matcher.is_ignored("keep.tmp"));
+        assert!(matcher.is_ignored("other.tmp"));
+
+        assert!(IgnoreMatcher::new(&["#include more"]).is_err());
+        // Only an `#include` followed by whitespace includes a file
+        let matcher = IgnoreMatcher::new(&["#includemore"]).unwrap();
+        assert!(matcher.is_ignored("#includemore"));
+    }
+
+    #[test]
+    fn test_include_loop() {
+        let result = IgnoreMatcher::with_includes(&["#include loop"], |_| {
+            Ok(vec!["#include loop".to_string()])
+        });
+        assert!(matches!(result, Err(Error::InvalidIgnorePatternError(_))));
+
+        // Reaching the same file by another path is still a cycle
+        let result = IgnoreMatcher::with_includes(&["#include dir/a"], |include| match include {
+            "dir/a" => Ok(vec!["#include ../dir/./a".to_string()]),
+            other => panic!("unexpected include {other}"),
+        });
+        assert!(matches!(result, Err(Error::InvalidIgnorePatternError(_))));
+    }
+
+    /// Includes are relative to the including file, and the same file may
+    /// be included by several files.
+    #[test]
+    fn test_nested_includes() {
+        let mut loaded = Vec::new();
+        let matcher = IgnoreMatcher::with_includes(
+            &["#include sub/first", "#include sub/second"],
+            |include| {
+                loaded.push(include.to_string());
+                Ok(match include {
+                    "sub/first" | "sub/second" => vec!["#include common".to_string()],
+                    "sub/common" => vec!["*.tmp".to_string()],
+                    other => panic!("unexpected include {other}"),
+                })
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            loaded,
+            ["sub/first", "sub/common", "sub/second", "sub/common"]
+        );
+        assert_eq!(matcher.patterns.len(), 2);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/.stignore");
+        let matcher = IgnoreMatcher::from_file(path).unwrap();
+
+        assert_eq!(matcher.patterns.len(), 3);
+        assert!(matcher.patterns[0].deletable);
+        assert!(matcher.is_ignored("photos/.DS_Store"));
+        assert!(!matcher.is_ignored("important.tmp"));
+        assert!(matcher.is_ignored("sub/important.tmp"));
+    }
+}
@@ -1,5 +1,13 @@
 mod client;
 pub use client::Client;
 pub use client::ClientBuilder;
+pub use event_stream::{DiskEventStream, EventStream};
+pub mod config_xml;
+pub mod diff;
 pub mod error;
+mod event_stream;
+mod http;
+pub mod ignore;
+pub mod reconcile;
+mod tls;
 pub mod types;
@@ -0,0 +1,513 @@
+//! Declarative management of the folders and devices of a Syncthing instance.
+//!
+//! A [`DesiredState`] describes which folders and devices should exist. It
+//! can be deserialized from any format supported by serde, e.g. YAML or TOML.
+//! Only the fields which are set in the desired state are managed, all others
+//! keep their current value. Folders and devices which are not part of the
+//! desired state are only deleted if [`prune`](DesiredState::prune) is set.
+//!
+//! ```no_run
+//! # async fn example(client: syncthing_rs::Client) -> syncthing_rs::error::Result<()> {
+//! use syncthing_rs::{
+//!     reconcile::DesiredState,
+//!     types::config::{NewDeviceConfiguration, NewFolderConfiguration},
+//! };
+//!
+//! let device_id = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD".parse()?;
+//! let desired = DesiredState {
+//!     folders: vec![
+//!         NewFolderConfiguration::new("docs".to_string(), "/data/docs".to_string())
+//!             .label("Documents".to_string()),
+//!     ],
+//!     devices: vec![NewDeviceConfiguration::new(device_id).name("laptop".to_string())],
+//!     prune: false,
+//! };
+//!
+//! // Dry-run
+//! let plan = desired.plan(&client).await?;
+//! println!("{plan}");
+//!
+//! plan.apply(&client).await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+use crate::{
+    Client,
+    diff::FieldChange,
+    error::Result,
+    types::{
+        config::{NewDeviceConfiguration, NewFolderConfiguration},
+        device_id::DeviceId,
+    },
+};
+
+/// The folders and devices which should exist on an instance.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DesiredState {
+    #[serde(default)]
+    pub folders: Vec<NewFolderConfiguration>,
+    #[serde(default)]
+    pub devices: Vec<NewDeviceConfiguration>,
+    /// Whether folders and devices which are not listed should be deleted.
+    /// The device of the instance itself is never deleted.
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// A single step of a [`Plan`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum Action {
+    #[serde(rename_all = "camelCase")]
+    CreateDevice { device: Box<NewDeviceConfiguration> },
+    #[serde(rename_all = "camelCase")]
+    UpdateDevice {
+        device: Box<NewDeviceConfiguration>,
+        /// The fields which differ from the current configuration.
+        changes: Vec<FieldChange>,
+    },
+    #[serde(rename_all = "camelCase")]
+    CreateFolder { folder: Box<NewFolderConfiguration> },
+    #[serde(rename_all = "camelCase")]
+    UpdateFolder {
+        folder: Box<NewFolderConfiguration>,
+        /// The fields which differ from the current configuration.
+        changes: Vec<FieldChange>,
+    },
+    #[serde(rename_all = "camelCase")]
+    DeleteFolder { id: String },
+    #[serde(rename_all = "camelCase")]
+    DeleteDevice {
+        #[serde(rename = "deviceID")]
+        device_id: DeviceId,
+    },
+}
+
+/// The actions required to reach a [`DesiredState`].
+///
+/// Devices are created before the folders which might be shared with
+/// them, and folders are deleted before devices.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Plan {
+    pub actions: Vec<Action>,
+}
+
+impl DesiredState {
+    /// Computes the [`Plan`] to reach this state from the current
+    /// configuration, without changing anything.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the configuration or the ID of the
+    /// instance cannot be retrieved.
+    pub async fn plan(&self, client: &Client) -> Result<Plan> {
+        let config = client.get_configuration().await?;
+        let my_id = client.get_id().await?;
+
+        let mut creates = Vec::new();
+        let mut updates = Vec::new();
+        let mut deletes = Vec::new();
+
+        for device in &self.devices {
+            match config
+                .devices
+                .iter()
+                .find(|d| d.device_id == *device.get_device_id())
+            {
+                None => creates.push(Action::CreateDevice {
+                    device: Box::new(device.clone()),
+                }),
+                Some(current) => {
+                    let changes = changes(to_value(current), to_value(device));
+                    if !changes.is_empty() {
+                        updates.push(Action::UpdateDevice {
+                            device: Box::new(device.clone()),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+
+        for folder in &self.folders {
+            match config.folders.iter().find(|f| f.id == *folder.get_id()) {
+                None => creates.push(Action::CreateFolder {
+                    folder: Box::new(folder.clone()),
+                }),
+                Some(current) => {
+                    let current = without_device(to_value(current), &my_id);
+                    let desired = without_device(to_value(folder), &my_id);
+                    let changes = changes(current, desired);
+                    if !changes.is_empty() {
+                        updates.push(Action::UpdateFolder {
+                            folder: Box::new(folder.clone()),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+
+        if self.prune {
+            for folder in &config.folders {
+                if !self.folders.iter().any(|f| *f.get_id() == folder.id) {
+                    deletes.push(Action::DeleteFolder {
+                        id: folder.id.clone(),
+                    });
+                }
+            }
+            for device in &config.devices {
+                if device.device_id != my_id
+                    && !self
+                        .devices
+                        .iter()
+                        .any(|d| *d.get_device_id() == device.device_id)
+                {
+                    deletes.push(Action::DeleteDevice {
+                        device_id: device.device_id,
+                    });
+                }
+            }
+        }
+
+        let mut actions = creates;
+        actions.append(&mut updates);
+        actions.append(&mut deletes);
+        Ok(Plan { actions })
+    }
+
+    /// Computes the [`Plan`] to reach this state and applies it, unless
+    /// `dry_run` is set. Returns the computed plan in both cases.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the plan cannot be computed or any action fails.
+    pub async fn reconcile(&self, client: &Client, dry_run: bool) -> Result<Plan> {
+        let plan = self.plan(client).await?;
+        if !dry_run {
+            plan.apply(client).await?;
+        }
+        Ok(plan)
+    }
+}
+
+impl Plan {
+    /// Whether the desired state is already reached.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Applies all actions in order. New folders and devices are posted,
+    /// existing ones are patched with only their
+    /// [`changes`](Action::UpdateFolder::changes), so all other fields,
+    /// including the path of a folder, keep their current value.
+    ///
+    /// Once applied, planning the same desired state again results
+    /// in an empty plan.
+    ///
+    /// # Errors
+    ///
+    /// This method stops at the first action that fails. The remaining
+    /// actions are not applied.
+    pub async fn apply(&self, client: &Client) -> Result<()> {
+        for action in &self.actions {
+            log::debug!("applying {action:?}");
+            match action {
+                Action::CreateDevice { device } => client.post_device(*device.clone()).await?,
+                Action::UpdateDevice { device, changes } => {
+                    client
+                        .patch_device(device.get_device_id(), to_patch(device, changes)?)
+                        .await?;
+                }
+                Action::CreateFolder { folder } => client.post_folder(*folder.clone()).await?,
+                Action::UpdateFolder { folder, changes } => {
+                    client
+                        .patch_folder(folder.get_id(), to_patch(folder, changes)?)
+                        .await?;
+                }
+                Action::DeleteFolder { id } => client.delete_folder(id).await?,
+                Action::DeleteDevice { device_id } => client.delete_device(device_id).await?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        for action in &self.actions {
+            match action {
+                Action::CreateDevice { device } => {
+                    writeln!(f, "+ device {}", device.get_device_id())?;
+                }
+                Action::UpdateDevice { device, changes } => {
+                    writeln!(f, "~ device {}", device.get_device_id())?;
+                    fmt_changes(f, changes)?;
+                }
+                Action::CreateFolder { folder } => writeln!(f, "+ folder {}", folder.get_id())?,
+                Action::UpdateFolder { folder, changes } => {
+                    writeln!(f, "~ folder {}", folder.get_id())?;
+                    fmt_changes(f, changes)?;
+                }
+                Action::DeleteFolder { id } => writeln!(f, "- folder {id}")?,
+                Action::DeleteDevice { device_id } => writeln!(f, "- device {device_id}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn fmt_changes(f: &mut fmt::Formatter<'_>, changes: &[FieldChange]) -> fmt::Result {
+    for change in changes {
+        writeln!(f, "    {}: {} -> {}", change.field, change.old, change.new)?;
+    }
+    Ok(())
+}
+
+fn to_value<T: Serialize>(value: &T) -> Value {
+    // The configuration types can always be represented as JSON
+    serde_json::to_value(value).expect("configuration is serializable")
+}
+
+/// Converts the `changes` of the fields set in `desired` into a patch.
+fn to_patch<P: DeserializeOwned>(desired: &impl Serialize, changes: &[FieldChange]) -> Result<P> {
+    let desired = to_value(desired);
+    // The desired value is used, as the changes of folders exclude this device
+    let fields: Map<String, Value> = changes
+        .iter()
+        .filter_map(|change| Some((change.field.clone(), desired.get(&change.field)?.clone())))
+        .collect();
+    Ok(serde_json::from_value(Value::Object(fields))?)
+}
+
+/// Returns the fields set in `desired` which differ from `current`.
+fn changes(current: Value, desired: Value) -> Vec<FieldChange> {
+    let Value::Object(desired) = desired else {
+        return Vec::new();
+    };
+    desired
+        .into_iter()
+        .filter_map(|(field, new)| {
+            let old = current.get(&field).cloned().unwrap_or_default();
+            (old != new).then_some(FieldChange { field, old, new })
+        })
+        .collect()
+}
+
+/// Syncthing always shares a folder with the instance itself, so it is
+/// removed from the folder's devices before comparing them.
+fn without_device(mut folder: Value, device_id: &DeviceId) -> Value {
+    if let Some(Value::Array(devices)) = folder.get_mut("devices") {
+        let device_id = device_id.to_string();
+        devices.retain(|d| d.get("deviceID").and_then(Value::as_str) != Some(&device_id));
+        devices.sort_by(|a, b| a["deviceID"].to_string().cmp(&b["deviceID"].to_string()));
+    }
+    folder
+}
+
+#[cfg(test)]
+mod tests {
+    use httpmock::prelude::*;
+
+    use super::*;
+    use crate::{ClientBuilder, types::config::FolderDeviceConfiguration};
+
+    // Device ID of the instance in tests/data/config.json
+    const MY_ID: &str = "6ZPJ5NK-PIUNKHW-UZ5RQB4-GVJC76Z-GLPVVFO-LQW5MZ4-KQR3UKK-D4JPEAQ";
+
+    // Example device id from the docs
+    const DEVICE_ID: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+
+    fn mock_instance(server: &MockServer) {
+        let config = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.json"),
+        )
+        .unwrap();
+        server.mock(|when, then| {
+            when.method(GET).path("/config");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body(config);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/noauth/health");
+            then.status(200)
+                .header("X-Syncthing-Id", MY_ID)
+                .body(r#"{"status": "OK"}"#);
+        });
+    }
+
+    fn client(server: &MockServer) -> Client {
+        ClientBuilder::new("")
+            .base_url(server.base_url())
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_plan_unchanged() {
+        let server = MockServer::start();
+        mock_instance(&server);
+
+        // Only the set fields are compared, and the instance itself is
+        // implicitly part of every folder
+        let desired = DesiredState {
+            folders: vec![
+                NewFolderConfiguration::new(
+                    "default".to_string(),
+                    "/var/syncthing/Sync".to_string(),
+                )
+                .devices(vec![]),
+            ],
+            devices: vec![NewDeviceConfiguration::new(MY_ID.parse().unwrap())],
+            prune: true,
+        };
+
+        let plan = desired.plan(&client(&server)).await.unwrap();
+        assert!(plan.is_empty(), "{plan}");
+        assert_eq!(plan.to_string(), "no changes\n");
+    }
+
+    #[tokio::test]
+    async fn test_plan() {
+        let server = MockServer::start();
+        mock_instance(&server);
+        let device_id: DeviceId = DEVICE_ID.parse().unwrap();
+
+        let desired = DesiredState {
+            folders: vec![
+                NewFolderConfiguration::new(
+                    "default".to_string(),
+                    "/var/syncthing/Sync".to_string(),
+                )
+                .label("Sync".to_string())
+                .devices(vec![FolderDeviceConfiguration {
+                    device_id,
+                    introduced_by: String::new(),
+                    encryption_password: String::new(),
+                }]),
+            ],
+            devices: vec![NewDeviceConfiguration::new(device_id)],
+            prune: false,
+        };
+
+        let plan = desired.plan(&client(&server)).await.unwrap();
+        assert_eq!(plan.actions.len(), 2);
+        assert!(matches!(plan.actions[0], Action::CreateDevice { .. }));
+        let Action::UpdateFolder { changes, .. } = &plan.actions[1] else {
+            panic!("expected a folder update, got {:?}", plan.actions[1]);
+        };
+        assert_eq!(
+            changes.iter().map(|c| c.field.as_str()).collect::<Vec<_>>(),
+            vec!["devices", "label"]
+        );
+        assert_eq!(
+            plan.to_string(),
+            format!(
+                "+ device {DEVICE_ID}\n~ folder default\n    devices: [] -> [{{\"deviceID\":\"{DEVICE_ID}\",\"encryptionPassword\":\"\",\"introducedBy\":\"\"}}]\n    label: \"Default Folder\" -> \"Sync\"\n"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_plan_prune() {
+        let server = MockServer::start();
+        mock_instance(&server);
+
+        let plan = DesiredState {
+            prune: true,
+            ..Default::default()
+        }
+        .plan(&client(&server))
+        .await
+        .unwrap();
+
+        // The instance itself must not be deleted
+        assert_eq!(
+            plan.actions,
+            vec![Action::DeleteFolder {
+                id: "default".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply() {
+        let server = MockServer::start();
+        let device_id: DeviceId = DEVICE_ID.parse().unwrap();
+
+        let post_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/config/devices")
+                .json_body(serde_json::json!({"deviceID": DEVICE_ID}));
+            then.status(200);
+        });
+        let patch_mock = server.mock(|when, then| {
+            when.method(PATCH)
+                .path("/config/folders/default")
+                .json_body(serde_json::json!({"label": "Sync"}));
+            then.status(200);
+        });
+        let delete_mock = server.mock(|when, then| {
+            when.method(DELETE).path("/config/folders/old");
+            then.status(200);
+        });
+
+        let plan = Plan {
+            actions: vec![
+                Action::CreateDevice {
+                    device: Box::new(NewDeviceConfiguration::new(device_id)),
+                },
+                Action::UpdateFolder {
+                    folder: Box::new(
+                        NewFolderConfiguration::new("default".to_string(), "/tmp".to_string())
+                            .label("Sync".to_string()),
+                    ),
+                    // The path is unchanged, so it must not be sent
+                    changes: vec![FieldChange {
+                        field: "label".to_string(),
+                        old: "Default Folder".into(),
+                        new: "Sync".into(),
+                    }],
+                },
+                Action::DeleteFolder {
+                    id: "old".to_string(),
+                },
+            ],
+        };
+        plan.apply(&client(&server)).await.unwrap();
+
+        post_mock.assert();
+        patch_mock.assert();
+        delete_mock.assert();
+    }
+
+    #[test]
+    fn test_deserialize_desired_state() {
+        let desired: DesiredState = serde_json::from_value(serde_json::json!({
+            "folders": [{
+                "id": "docs",
+                "path": "/data/docs",
+                "type": "sendonly",
+                "devices": [{"deviceID": DEVICE_ID}]
+            }],
+            "devices": [{"deviceID": DEVICE_ID, "name": "laptop"}]
+        }))
+        .unwrap();
+
+        assert!(!desired.prune);
+        assert_eq!(desired.folders[0].get_id(), "docs");
+        assert!(desired.folders[0].get_label().is_none());
+        assert_eq!(desired.devices[0].get_name(), &Some("laptop".to_string()));
+    }
+}
@@ -0,0 +1,145 @@
+//! TLS configuration for connecting to a GUI with a pinned certificate.
+//!
+//! Syncthing generates a self-signed certificate for its GUI, which is
+//! not valid for the address it is reached by. Instead of verifying the
+//! chain and host name, a pinned certificate is identified by the SHA-256
+//! fingerprint of its DER encoding, as printed by
+//! `openssl x509 -noout -fingerprint -sha256`.
+use std::sync::Arc;
+
+use data_encoding::HEXUPPER_PERMISSIVE;
+use rustls::{
+    DigitallySignedStruct, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// Parses a SHA-256 fingerprint in hexadecimal, optionally separated by colons.
+pub(crate) fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 32]> {
+    let hex = fingerprint.replace(':', "");
+    HEXUPPER_PERMISSIVE
+        .decode(hex.as_bytes())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            Error::InvalidCertificateError(format!("invalid SHA-256 fingerprint: {fingerprint}"))
+        })
+}
+
+/// Returns a TLS configuration, which only accepts the certificate
+/// with the given SHA-256 `fingerprint`.
+pub(crate) fn pinned_config(fingerprint: [u8; 32]) -> Result<rustls::ClientConfig> {
+    let provider = CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()));
+    let verifier = PinnedCertificateVerifier {
+        fingerprint,
+        provider: provider.clone(),
+    };
+    let mut config = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| Error::InvalidCertificateError(e.to_string()))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth();
+    // A preconfigured backend is used as is, so reqwest does not set this
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+#[derive(Debug)]
+struct PinnedCertificateVerifier {
+    fingerprint: [u8; 32],
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if Sha256::digest(end_entity).as_slice() == self.fingerprint {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let expected = [
+            0x0e, 0x73, 0xff, 0x0e, 0x1c, 0x03, 0xb6, 0xf0, 0x0e, 0x37, 0xdd, 0xc8, 0xae, 0x5f,
+            0xe5, 0x4d, 0x5c, 0x1c, 0xbd, 0x10, 0x3f, 0x11, 0xad, 0xca, 0xd0, 0x16, 0x20, 0x69,
+            0x29, 0x77, 0xe2, 0x46,
+        ];
+        assert_eq!(
+            parse_fingerprint("0E:73:FF:0E:1C:03:B6:F0:0E:37:DD:C8:AE:5F:E5:4D:5C:1C:BD:10:3F:11:AD:CA:D0:16:20:69:29:77:E2:46").unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_fingerprint("0e73ff0e1c03b6f00e37ddc8ae5fe54d5c1cbd103f11adcad01620692977e246")
+                .unwrap(),
+            expected
+        );
+
+        for invalid in [
+            "",
+            "0E:73",
+            "zz:73:FF:0E:1C:03:B6:F0:0E:37:DD:C8:AE:5F:E5:4D:5C:1C:BD:10:3F:11:AD:CA:D0:16:20:69:29:77:E2:46",
+        ] {
+            assert!(matches!(
+                parse_fingerprint(invalid),
+                Err(Error::InvalidCertificateError(_))
+            ));
+        }
+    }
+}
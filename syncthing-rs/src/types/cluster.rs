@@ -5,10 +5,12 @@ use std::collections::HashMap;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
+use super::device_id::DeviceId;
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct PendingDevices {
     #[serde(flatten)]
-    pub devices: HashMap<String, PendingDevice>,
+    pub devices: HashMap<DeviceId, PendingDevice>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -28,7 +30,7 @@ pub struct PendingFolders {
 #[serde(rename_all = "camelCase")]
 pub struct PendingFolder {
     /// Maps deviceID to the information about that folder on that device
-    pub offered_by: HashMap<String, PendingFolderOfferer>,
+    pub offered_by: HashMap<DeviceId, PendingFolderOfferer>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -1,9 +1,13 @@
 //! Config types, as defined [here](https://docs.syncthing.net/users/config.html)
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
+use data_encoding::HEXLOWER;
 use serde::{Deserialize, Serialize};
-use syncthing_macros::New;
+use sha2::{Digest, Sha256};
+use syncthing_macros::{New, Patch};
+
+use super::device_id::DeviceId;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Size {
@@ -11,6 +15,41 @@ pub struct Size {
     pub unit: String,
 }
 
+/// Whether the configuration has changes which require a restart to take effect.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartRequired {
+    pub requires_restart: bool,
+}
+
+/// Identifies the state of a [`Configuration`] at the time it was read.
+///
+/// Besides the config `version`, this contains a SHA-256 hash of the
+/// configuration as returned by Syncthing, so any change can be detected.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigRevision {
+    pub version: u64,
+    pub hash: String,
+}
+
+impl ConfigRevision {
+    /// Computes the revision of the raw JSON configuration `json`,
+    /// which contains the given `version`.
+    pub(crate) fn new(version: u64, json: &[u8]) -> Self {
+        Self {
+            version,
+            hash: HEXLOWER.encode(&Sha256::digest(json)),
+        }
+    }
+}
+
+impl fmt::Display for ConfigRevision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hash = self.hash.get(..12).unwrap_or(&self.hash);
+        write!(f, "version {} ({hash})", self.version)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Configuration {
@@ -19,11 +58,22 @@ pub struct Configuration {
     pub devices: Vec<DeviceConfiguration>,
     pub gui: GuiConfiguration,
     pub ldap: LDAPConfiguration,
+    pub options: OptionsConfiguration,
     pub remote_ignored_devices: Vec<ObservedDevice>,
     pub defaults: Defaults,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New)]
+impl Configuration {
+    /// Computes all changes from `self` to `new`, see [`diff`](crate::diff::diff).
+    #[must_use]
+    pub fn diff(&self, new: &Configuration) -> Vec<crate::diff::Change> {
+        crate::diff::diff(self, new)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New, Patch)]
+// Used in a `DesiredState` of the reconciler
+#[new(deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FolderConfiguration {
     #[required]
@@ -52,7 +102,11 @@ pub struct FolderConfiguration {
     pub puller_pause_s: i64,
     pub max_conflicts: i64,
     pub disable_sparse_files: bool,
+    #[serde(default)]
+    pub disable_temp_indexes: bool,
     pub paused: bool,
+    #[serde(default)]
+    pub weak_hash_threshold_pct: i64,
     pub marker_name: String,
     pub copy_ownership_from_parent: bool,
     pub mod_time_window_s: i64,
@@ -90,8 +144,10 @@ pub enum FolderType {
 #[serde(rename_all = "camelCase")]
 pub struct FolderDeviceConfiguration {
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
+    #[serde(default)]
     pub introduced_by: String,
+    #[serde(default)]
     pub encryption_password: String,
 }
 
@@ -141,17 +197,26 @@ pub enum CopyRangeMethod {
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct XattrFilter {
-    pub entries: Vec<String>,
+    pub entries: Vec<XattrFilterEntry>,
     pub max_single_entry_size: u64,
     pub max_total_size: u64,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New)]
+/// Extended attributes with a name matching `match` are synced if `permit` is set.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct XattrFilterEntry {
+    pub r#match: String,
+    pub permit: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New, Patch)]
+// Used in a `DesiredState` of the reconciler
+#[new(deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeviceConfiguration {
     #[required]
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
     pub name: String,
     // According to the docs "at least one is required",
     // however it does not seem to get enforced.
@@ -174,6 +239,89 @@ pub struct DeviceConfiguration {
     pub num_connections: i64,
 }
 
+/// Template for new devices, as contained in [`Defaults`].
+///
+/// Syncthing leaves the device ID of the template empty, so it is `None`
+/// unless it was set explicitly.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultDeviceConfiguration {
+    #[serde(rename = "deviceID", with = "empty_device_id")]
+    pub device_id: Option<DeviceId>,
+    pub name: String,
+    pub addresses: Vec<String>,
+    pub compression: Compression,
+    pub cert_name: String,
+    pub introducer: bool,
+    pub skip_introduction_removals: bool,
+    pub introduced_by: String,
+    pub paused: bool,
+    pub allowed_networks: Vec<String>,
+    pub auto_accept_folders: bool,
+    pub max_send_kbps: i64,
+    pub max_recv_kbps: i64,
+    pub ignored_folders: Vec<ObservedFolder>,
+    pub max_request_ki_b: i64,
+    pub untrusted: bool,
+    #[serde(rename = "remoteGUIPort")]
+    pub remote_gui_port: i64,
+    pub num_connections: i64,
+}
+
+impl DefaultDeviceConfiguration {
+    /// Instantiates the template for the device with the given ID.
+    #[must_use]
+    pub fn into_device(self, device_id: DeviceId) -> DeviceConfiguration {
+        DeviceConfiguration {
+            device_id,
+            name: self.name,
+            addresses: self.addresses,
+            compression: self.compression,
+            cert_name: self.cert_name,
+            introducer: self.introducer,
+            skip_introduction_removals: self.skip_introduction_removals,
+            introduced_by: self.introduced_by,
+            paused: self.paused,
+            allowed_networks: self.allowed_networks,
+            auto_accept_folders: self.auto_accept_folders,
+            max_send_kbps: self.max_send_kbps,
+            max_recv_kbps: self.max_recv_kbps,
+            ignored_folders: self.ignored_folders,
+            max_request_ki_b: self.max_request_ki_b,
+            untrusted: self.untrusted,
+            remote_gui_port: self.remote_gui_port,
+            num_connections: self.num_connections,
+        }
+    }
+}
+
+/// (De)serializes an optional device ID as an empty string if it is `None`.
+mod empty_device_id {
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    use crate::types::device_id::DeviceId;
+
+    pub(super) fn serialize<S: Serializer>(
+        id: &Option<DeviceId>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match id {
+            Some(id) => serializer.collect_str(id),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DeviceId>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        if s.is_empty() {
+            return Ok(None);
+        }
+        s.parse().map(Some).map_err(de::Error::custom)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum Compression {
@@ -190,7 +338,7 @@ pub struct ObservedFolder {
     pub label: String,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New)]
 #[serde(rename_all = "camelCase")]
 pub struct GuiConfiguration {
     pub enabled: bool,
@@ -205,6 +353,8 @@ pub struct GuiConfiguration {
     pub api_key: String,
     pub insecure_admin_access: bool,
     pub theme: String,
+    #[serde(default)]
+    pub debugging: bool,
     pub insecure_skip_hostcheck: bool,
     pub insecure_allow_frame_loading: bool,
     pub send_basic_auth_prompt: bool,
@@ -218,7 +368,7 @@ pub enum AuthMode {
     LDAP,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New)]
 #[serde(rename_all = "camelCase")]
 pub struct LDAPConfiguration {
     pub address: String,
@@ -240,12 +390,12 @@ pub enum LDAPTransport {
     StartTLS,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, New)]
 #[serde(rename_all = "camelCase")]
 pub struct OptionsConfiguration {
-    pub listen_address: Vec<String>,
+    pub listen_addresses: Vec<String>,
     pub global_announce_servers: Vec<String>,
-    pub global_annouce_enabled: bool,
+    pub global_announce_enabled: bool,
     pub local_announce_enabled: bool,
     pub local_announce_port: i64,
     #[serde(rename = "localAnnounceMCAddr")]
@@ -261,17 +411,17 @@ pub struct OptionsConfiguration {
     pub nat_renewal_minutes: i64,
     pub nat_timeout_seconds: i64,
     pub ur_accepted: i64,
-    pub ur_screen: i64,
+    pub ur_seen: i64,
     pub ur_unique_id: String,
     #[serde(rename = "urURL")]
     pub ur_url: String,
     pub ur_post_insecurely: bool,
-    pub ur_initial_dleay_s: i64,
-    pub auto_upgraade_interval_h: i64,
+    pub ur_initial_delay_s: i64,
+    pub auto_upgrade_interval_h: i64,
     pub upgrade_to_pre_releases: bool,
     pub keep_temporaries_h: i64,
     pub cache_ignored_files: bool,
-    pub progress_update_inteval_s: i64,
+    pub progress_update_interval_s: i64,
     pub limit_bandwidth_in_lan: bool,
     pub min_home_disk_free: Size,
     #[serde(rename = "releasesURL")]
@@ -280,7 +430,7 @@ pub struct OptionsConfiguration {
     pub overwrite_remote_device_names_on_connect: bool,
     pub temp_index_min_blocks: i64,
     #[serde(rename = "unackedNotificationIDs")]
-    pub unacked_notifications_ids: Vec<String>,
+    pub unacked_notification_ids: Vec<String>,
     pub traffic_class: i64,
     pub set_low_priority: bool,
     pub max_folder_concurrency: i64,
@@ -291,7 +441,8 @@ pub struct OptionsConfiguration {
     pub stun_keepalive_min_s: i64,
     pub stun_servers: Vec<String>,
     pub database_tuning: Tuning,
-    pub max_concurrent_incoming_requests_ki_b: i64,
+    #[serde(rename = "maxConcurrentIncomingRequestKiB")]
+    pub max_concurrent_incoming_request_ki_b: i64,
     #[serde(rename = "announceLANAddresses")]
     pub announce_lan_addresses: bool,
     pub send_full_index_on_upgrade: bool,
@@ -300,6 +451,9 @@ pub struct OptionsConfiguration {
     pub audit_file: String,
     pub connection_limit_enough: i64,
     pub connection_limit_max: i64,
+    #[serde(default)]
+    #[serde(rename = "insecureAllowOldTLSVersions")]
+    pub insecure_allow_old_tls_versions: bool,
     pub connection_priority_tcp_lan: i64,
     pub connection_priority_quic_lan: i64,
     pub connection_priority_tcp_wan: i64,
@@ -321,7 +475,7 @@ pub enum Tuning {
 pub struct ObservedDevice {
     pub time: chrono::DateTime<chrono::Utc>,
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
     pub name: String,
     pub address: String,
 }
@@ -330,7 +484,7 @@ pub struct ObservedDevice {
 #[serde(rename_all = "camelCase")]
 pub struct Defaults {
     pub folder: FolderConfiguration,
-    pub device: DeviceConfiguration,
+    pub device: DefaultDeviceConfiguration,
     pub ignores: Ignores,
 }
 
@@ -339,3 +493,99 @@ pub struct Defaults {
 pub struct Ignores {
     pub lines: Vec<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    fn config_json() -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.json");
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    /// Asserts that every field in `serialized` is also present in `original`
+    /// with the same value. Fields we do not know about are ignored, but any
+    /// misspelt field shows up as missing.
+    fn assert_known_fields(serialized: &Value, original: &Value, path: &str) {
+        match (serialized, original) {
+            (Value::Object(serialized), Value::Object(original)) => {
+                for (key, value) in serialized {
+                    let path = format!("{path}.{key}");
+                    let original = original
+                        .get(key)
+                        .unwrap_or_else(|| panic!("{path} does not exist in the original"));
+                    assert_known_fields(value, original, &path);
+                }
+            }
+            (Value::Array(serialized), Value::Array(original)) => {
+                assert_eq!(serialized.len(), original.len(), "{path}");
+                for (i, (value, original)) in serialized.iter().zip(original).enumerate() {
+                    assert_known_fields(value, original, &format!("{path}[{i}]"));
+                }
+            }
+            // Floats like 10.0 are serialized differently than Syncthing's 10
+            (Value::Number(serialized), Value::Number(original)) => {
+                assert_eq!(serialized.as_f64(), original.as_f64(), "{path}");
+            }
+            (serialized, original) => assert_eq!(serialized, original, "{path}"),
+        }
+    }
+
+    #[test]
+    fn test_configuration_round_trip() {
+        let original = config_json();
+        let config: Configuration = serde_json::from_value(original.clone()).unwrap();
+
+        assert_eq!(config.version, 37);
+        // Syncthing leaves the ID of the default device empty
+        assert_eq!(config.defaults.device.device_id, None);
+
+        let serialized = serde_json::to_value(&config).unwrap();
+        assert_known_fields(&serialized, &original, "");
+        assert_eq!(
+            serde_json::from_value::<Configuration>(serialized).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn test_options_round_trip() {
+        let original = config_json()["options"].clone();
+        let options: OptionsConfiguration = serde_json::from_value(original.clone()).unwrap();
+
+        assert!(options.global_announce_enabled);
+        assert_eq!(options.listen_addresses, vec!["default"]);
+        assert_eq!(options.ur_initial_delay_s, 1800);
+        assert_eq!(options.auto_upgrade_interval_h, 12);
+        assert_eq!(options.progress_update_interval_s, 5);
+        assert_eq!(
+            options.unacked_notification_ids,
+            vec!["authenticationUserAndPassword"]
+        );
+        assert_eq!(options.database_tuning, Tuning::Auto);
+
+        let serialized = serde_json::to_value(&options).unwrap();
+        assert_known_fields(&serialized, &original, "options");
+    }
+
+    #[test]
+    fn test_options_patch() {
+        let patch = NewOptionsConfiguration::from(
+            serde_json::from_value::<OptionsConfiguration>(config_json()["options"].clone())
+                .unwrap(),
+        );
+        assert_eq!(patch.get_auto_upgrade_interval_h(), &Some(12));
+
+        let patch = NewOptionsConfiguration::new()
+            .global_announce_enabled(false)
+            .max_recv_kbps(100);
+        assert_eq!(
+            serde_json::to_value(&patch).unwrap(),
+            serde_json::json!({"globalAnnounceEnabled": false, "maxRecvKbps": 100})
+        );
+    }
+}
@@ -1,5 +1,9 @@
 //! All types required for the db endpoints
-use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::HashMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+
+use super::device_id::DeviceId;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -10,6 +14,405 @@ pub struct Completion {
     pub global_items: i64,
     pub need_items: i64,
     pub need_deletes: i64,
-    pub remote_state: String,
+    pub remote_state: RemoteState,
     pub sequence: i64,
 }
+
+/// Whether a remote device shares a folder, as reported in its [`Completion`].
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteState {
+    Valid,
+    Paused,
+    NotSharing,
+    /// Any other state, including `unknown`, which Syncthing reports if the
+    /// device is not connected.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// State of a folder, as reported in its [`FolderSummary`] and the
+/// [`StateChanged`](super::events::EventType::StateChanged) event.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FolderState {
+    Idle,
+    Scanning,
+    ScanWaiting,
+    SyncWaiting,
+    SyncPreparing,
+    Syncing,
+    Cleaning,
+    CleanWaiting,
+    Error,
+    /// Any other state, including `unknown`, e.g. `Unknown("unknown".to_string())`.
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// Summary of a folder, as returned by `/rest/db/status` and
+/// contained in the `FolderSummary` event.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderSummary {
+    pub errors: i64,
+    pub global_files: i64,
+    pub global_directories: i64,
+    pub global_symlinks: i64,
+    pub global_deleted: i64,
+    pub global_bytes: i64,
+    pub global_total_items: i64,
+    pub local_files: i64,
+    pub local_directories: i64,
+    pub local_symlinks: i64,
+    pub local_deleted: i64,
+    pub local_bytes: i64,
+    pub local_total_items: i64,
+    pub need_files: i64,
+    pub need_directories: i64,
+    pub need_symlinks: i64,
+    pub need_deletes: i64,
+    pub need_bytes: i64,
+    pub need_total_items: i64,
+    pub receive_only_changed_files: i64,
+    pub receive_only_changed_directories: i64,
+    pub receive_only_changed_symlinks: i64,
+    pub receive_only_changed_deletes: i64,
+    pub receive_only_changed_bytes: i64,
+    pub receive_only_total_items: i64,
+    pub in_sync_files: i64,
+    pub in_sync_bytes: i64,
+    pub state: FolderState,
+    pub state_changed: chrono::DateTime<chrono::Utc>,
+    pub error: String,
+    pub sequence: i64,
+    pub remote_sequence: HashMap<DeviceId, i64>,
+    pub ignore_patterns: bool,
+    pub watch_error: String,
+}
+
+/// Ignore patterns of a folder, as returned by `/rest/db/ignores`.
+///
+/// Use [`IgnoreMatcher`](crate::ignore::IgnoreMatcher) to evaluate them locally.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FolderIgnores {
+    /// Lines of the `.stignore` file.
+    pub ignore: Option<Vec<String>>,
+    /// Patterns with all `#include` directives resolved.
+    pub expanded: Option<Vec<String>>,
+    pub error: Option<String>,
+}
+
+/// Selects a single page of a paginated db endpoint.
+///
+/// Pages start at `1`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pagination {
+    pub page: u64,
+    pub per_page: u64,
+}
+
+impl Pagination {
+    pub fn new(page: u64, per_page: u64) -> Self {
+        Self { page, per_page }
+    }
+
+    pub(crate) fn query(&self) -> String {
+        format!("&page={}&perpage={}", self.page, self.per_page)
+    }
+}
+
+/// Implemented by every page returned from a paginated endpoint,
+/// so that streams know when the last page has been reached.
+pub(crate) trait Paginated {
+    fn num_items(&self) -> usize;
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum FileInfoType {
+    #[serde(rename = "FILE_INFO_TYPE_FILE")]
+    File,
+    #[serde(rename = "FILE_INFO_TYPE_DIRECTORY")]
+    Directory,
+    #[serde(rename = "FILE_INFO_TYPE_SYMLINK_FILE")]
+    SymlinkFile, // Deprecated
+    #[serde(rename = "FILE_INFO_TYPE_SYMLINK_DIRECTORY")]
+    SymlinkDirectory, // Deprecated
+    #[serde(rename = "FILE_INFO_TYPE_SYMLINK")]
+    Symlink,
+}
+
+/// Information about a single file in the database.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: FileInfoType,
+    pub size: i64,
+    pub deleted: bool,
+    pub invalid: bool,
+    pub ignored: bool,
+    pub must_rescan: bool,
+    pub no_permissions: bool,
+    /// Octal representation, only set if the file has permission bits.
+    #[serde(default)]
+    pub permissions: Option<String>,
+    pub modified: chrono::DateTime<chrono::Utc>,
+    /// Short ID of the device which last modified the file.
+    pub modified_by: String,
+    pub sequence: i64,
+    pub num_blocks: Option<i64>,
+    pub version: VersionVector,
+    pub local_flags: u32,
+    /// Base64 encoded hash over all blocks of the file.
+    #[serde(default)]
+    pub blocks_hash: Option<String>,
+}
+
+/// A single counter of a [`VersionVector`], serialized as `SHORTID:value`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Counter {
+    /// Short ID of the device which made the change.
+    pub id: String,
+    pub value: u64,
+}
+
+impl FromStr for Counter {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (id, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("invalid version counter: {s}"))?;
+        let value = value
+            .parse()
+            .map_err(|_| format!("invalid version counter: {s}"))?;
+        Ok(Self {
+            id: id.to_string(),
+            value,
+        })
+    }
+}
+
+impl fmt::Display for Counter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.id, self.value)
+    }
+}
+
+impl Serialize for Counter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Counter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+/// The version vector of a file, containing one [`Counter`] for
+/// every device that changed the file.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct VersionVector(pub Vec<Counter>);
+
+impl VersionVector {
+    /// Returns the value of the counter of the device with the short ID `id`,
+    /// or `0` if the device never changed the file.
+    #[must_use]
+    pub fn counter(&self, id: &str) -> u64 {
+        self.0.iter().find(|c| c.id == id).map_or(0, |c| c.value)
+    }
+
+    /// Compares two version vectors. Returns `None` if the vectors are
+    /// concurrent, meaning that both contain changes the other one does
+    /// not know about, which results in a conflict.
+    #[must_use]
+    pub fn compare(&self, other: &Self) -> Option<Ordering> {
+        let mut result = Ordering::Equal;
+        let ids = self.0.iter().chain(other.0.iter()).map(|c| c.id.as_str());
+        for id in ids {
+            match (result, self.counter(id).cmp(&other.counter(id))) {
+                (_, Ordering::Equal) => (),
+                (Ordering::Equal, ord) => result = ord,
+                (current, ord) if current != ord => return None,
+                _ => (),
+            }
+        }
+        Some(result)
+    }
+}
+
+/// Device which has a file available, as returned by `/rest/db/file`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Availability {
+    pub id: DeviceId,
+    pub from_temporary: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct MtimeMapping {
+    pub real: chrono::DateTime<chrono::Utc>,
+    #[serde(rename = "virtual")]
+    pub virtual_: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Mtime {
+    pub err: Option<String>,
+    pub value: MtimeMapping,
+}
+
+/// Local and global version of a single file, as returned by `/rest/db/file`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FileDetails {
+    pub availability: Option<Vec<Availability>>,
+    pub global: FileInfo,
+    /// Has an empty `name` if the file does not exist locally.
+    pub local: FileInfo,
+    pub mtime: Mtime,
+}
+
+/// An entry of the tree returned by `/rest/db/browse`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrowseEntry {
+    pub name: String,
+    pub mod_time: chrono::DateTime<chrono::Utc>,
+    pub size: i64,
+    #[serde(rename = "type")]
+    pub ty: FileInfoType,
+    /// Only set for directories, if the requested depth allows it.
+    #[serde(default)]
+    pub children: Vec<BrowseEntry>,
+}
+
+/// Files needed by the local device, as returned by `/rest/db/need`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Need {
+    /// Files currently being downloaded.
+    pub progress: Vec<FileInfo>,
+    /// Files queued for download next.
+    pub queued: Vec<FileInfo>,
+    /// Remaining files.
+    pub rest: Vec<FileInfo>,
+    pub page: u64,
+    #[serde(rename = "perpage")]
+    pub per_page: u64,
+}
+
+impl Paginated for Need {
+    fn num_items(&self) -> usize {
+        self.progress.len() + self.queued.len() + self.rest.len()
+    }
+}
+
+/// A page of files, as returned by `/rest/db/remoteneed`
+/// and `/rest/db/localchanged`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct FilePage {
+    pub files: Vec<FileInfo>,
+    pub page: u64,
+    #[serde(rename = "perpage")]
+    pub per_page: u64,
+}
+
+impl Paginated for FilePage {
+    fn num_items(&self) -> usize {
+        self.files.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_state() {
+        for (json, state) in [
+            ("\"valid\"", RemoteState::Valid),
+            ("\"notSharing\"", RemoteState::NotSharing),
+            ("\"unknown\"", RemoteState::Unknown("unknown".to_string())),
+        ] {
+            assert_eq!(serde_json::from_str::<RemoteState>(json).unwrap(), state);
+            assert_eq!(serde_json::to_string(&state).unwrap(), json);
+        }
+    }
+
+    fn vector(counters: &[(&str, u64)]) -> VersionVector {
+        VersionVector(
+            counters
+                .iter()
+                .map(|(id, value)| Counter {
+                    id: id.to_string(),
+                    value: *value,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_version_vector_serde() {
+        let json = r#"["MFZWI3D:1","P56IOI7:12"]"#;
+        let parsed: VersionVector = serde_json::from_str(json).unwrap();
+
+        assert_eq!(parsed, vector(&[("MFZWI3D", 1), ("P56IOI7", 12)]));
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+        assert!(serde_json::from_str::<VersionVector>(r#"["MFZWI3D"]"#).is_err());
+    }
+
+    #[test]
+    fn test_version_vector_compare() {
+        let base = vector(&[("MFZWI3D", 1)]);
+        let newer = vector(&[("MFZWI3D", 1), ("P56IOI7", 1)]);
+        let concurrent = vector(&[("MFZWI3D", 2)]);
+
+        assert_eq!(base.compare(&base), Some(Ordering::Equal));
+        assert_eq!(base.compare(&newer), Some(Ordering::Less));
+        assert_eq!(newer.compare(&base), Some(Ordering::Greater));
+        assert_eq!(newer.compare(&concurrent), None);
+        assert_eq!(
+            VersionVector::default().compare(&base),
+            Some(Ordering::Less)
+        );
+    }
+
+    #[test]
+    fn test_browse_tree() {
+        let json = r#"
+[
+  {
+    "modTime": "2020-10-02T23:48:52.076996974+02:00",
+    "name": "directory",
+    "children": [
+      {
+        "modTime": "2020-10-02T23:48:52.076996974+02:00",
+        "name": "subdirectory",
+        "size": 128,
+        "type": "FILE_INFO_TYPE_DIRECTORY"
+      }
+    ],
+    "size": 128,
+    "type": "FILE_INFO_TYPE_DIRECTORY"
+  },
+  {
+    "modTime": "2020-10-02T23:48:52.076996974+02:00",
+    "name": "file.txt",
+    "size": 13,
+    "type": "FILE_INFO_TYPE_FILE"
+  }
+]
+"#;
+        let tree: Vec<BrowseEntry> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].children[0].name, "subdirectory");
+        assert_eq!(tree[0].children[0].ty, FileInfoType::Directory);
+        assert!(tree[1].children.is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! Typed device IDs, as defined [here](https://docs.syncthing.net/dev/device-ids.html)
+use std::{fmt, path::Path, str::FromStr};
+
+use data_encoding::{BASE32_NOPAD, BASE64};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Error, Result};
+
+/// The alphabet used by Syncthing's base32 encoding and Luhn check characters.
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Length of a device ID without check characters and dashes.
+const RAW_LEN: usize = 52;
+
+/// Length of a device ID with check characters, but without dashes.
+const LUHN_LEN: usize = 56;
+
+/// Every group of this many characters is followed by a check character.
+const LUHN_GROUP: usize = 13;
+
+/// Length of the dash-separated groups in the canonical form.
+const CHUNK_LEN: usize = 7;
+
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+/// A Syncthing device ID.
+///
+/// Internally, the ID is the SHA-256 hash of the device certificate.
+/// It can be parsed from both the dashed and the undashed form, with or
+/// without the Luhn check characters. The check characters are verified
+/// if present. Formatting always returns the canonical form, e.g.
+/// `MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD`.
+///
+/// ```
+/// use syncthing_rs::types::device_id::DeviceId;
+///
+/// let id: DeviceId = "mfzwi3dbonsgycyltmrwgc43enr5qxgzdmmfzwi3dpbonsgyyltmrwad"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(
+///     id.to_string(),
+///     "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD"
+/// );
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceId([u8; 32]);
+
+impl DeviceId {
+    /// Creates a device ID from the raw SHA-256 hash of a certificate.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the raw SHA-256 hash this ID consists of.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Computes the device ID of a DER encoded certificate.
+    ///
+    /// The certificate itself is not validated, the ID is simply the
+    /// SHA-256 hash of `der`.
+    #[must_use]
+    pub fn from_der(der: &[u8]) -> Self {
+        Self(Sha256::digest(der).into())
+    }
+
+    /// Computes the device ID of the first certificate in a PEM file,
+    /// such as Syncthing's `cert.pem`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if `pem` does not contain a certificate
+    /// or it is not valid base64.
+    pub fn from_pem(pem: &str) -> Result<Self> {
+        let start = pem
+            .find(PEM_BEGIN)
+            .ok_or_else(|| Error::InvalidCertificateError("no PEM certificate found".into()))?
+            + PEM_BEGIN.len();
+        let len = pem[start..]
+            .find(PEM_END)
+            .ok_or_else(|| Error::InvalidCertificateError("unterminated PEM certificate".into()))?;
+        let body: String = pem[start..start + len]
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect();
+        let der = BASE64
+            .decode(body.as_bytes())
+            .map_err(|e| Error::InvalidCertificateError(e.to_string()))?;
+        Ok(Self::from_der(&der))
+    }
+
+    /// Computes the device ID of the certificate stored at `path`, which
+    /// can either be PEM or DER encoded. This allows to know the ID of a
+    /// device before its Syncthing instance has ever been started.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the file cannot be read or contains an
+    /// invalid PEM certificate.
+    pub fn from_certificate_file(path: impl AsRef<Path>) -> Result<Self> {
+        let content = std::fs::read(path)?;
+        match std::str::from_utf8(&content) {
+            Ok(pem) if pem.contains(PEM_BEGIN) => Self::from_pem(pem),
+            _ => Ok(Self::from_der(&content)),
+        }
+    }
+}
+
+/// Calculates the Luhn mod 32 check character of `s`, which must only
+/// consist of characters from [`ALPHABET`].
+fn luhn32(s: &[u8]) -> Option<u8> {
+    let mut factor = 1;
+    let mut sum = 0;
+    for c in s {
+        let codepoint = ALPHABET.iter().position(|a| a == c)?;
+        let addend = factor * codepoint;
+        factor = if factor == 2 { 1 } else { 2 };
+        sum += addend / 32 + addend % 32;
+    }
+    Some(ALPHABET[(32 - sum % 32) % 32])
+}
+
+/// Appends a check character to every group of [`LUHN_GROUP`] characters.
+fn luhnify(s: &[u8]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(LUHN_LEN);
+    for group in s.chunks(LUHN_GROUP) {
+        res.extend_from_slice(group);
+        // The input is always produced by our own encoder
+        res.push(luhn32(group).expect("valid base32 alphabet"));
+    }
+    res
+}
+
+/// Verifies and strips the check character of every group.
+fn unluhnify(s: &[u8]) -> Option<Vec<u8>> {
+    let mut res = Vec::with_capacity(RAW_LEN);
+    for group in s.chunks(LUHN_GROUP + 1) {
+        let (data, check) = group.split_at(LUHN_GROUP);
+        if luhn32(data)? != check[0] {
+            return None;
+        }
+        res.extend_from_slice(data);
+    }
+    Some(res)
+}
+
+impl FromStr for DeviceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidDeviceIdError(s.to_string());
+
+        // Same normalization as Syncthing: ignore separators, fix case and
+        // replace characters that are commonly mistyped.
+        let cleaned: Vec<u8> = s
+            .bytes()
+            .filter(|c| *c != b'-' && *c != b' ')
+            .map(|c| match c.to_ascii_uppercase() {
+                b'0' => b'O',
+                b'1' => b'I',
+                b'8' => b'B',
+                c => c,
+            })
+            .collect();
+
+        let raw = match cleaned.len() {
+            LUHN_LEN => unluhnify(&cleaned).ok_or_else(invalid)?,
+            RAW_LEN => cleaned,
+            _ => return Err(invalid()),
+        };
+
+        let bytes = BASE32_NOPAD.decode(&raw).map_err(|_| invalid())?;
+        Ok(Self(bytes.try_into().map_err(|_| invalid())?))
+    }
+}
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoded = luhnify(BASE32_NOPAD.encode(&self.0).as_bytes());
+        for (i, chunk) in encoded.chunks(CHUNK_LEN).enumerate() {
+            if i > 0 {
+                f.write_str("-")?;
+            }
+            // The encoding only produces ASCII characters
+            f.write_str(std::str::from_utf8(chunk).map_err(|_| fmt::Error)?)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DeviceId({self})")
+    }
+}
+
+impl TryFrom<&str> for DeviceId {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for DeviceId {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<DeviceId> for String {
+    fn from(value: DeviceId) -> Self {
+        value.to_string()
+    }
+}
+
+impl Serialize for DeviceId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeviceId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Example device id from the docs
+    const DEVICE_ID: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+
+    // Device id of the certificate in tests/data
+    const CERT_DEVICE_ID: &str = "6ZPJ5NK-PIUNKHW-UZ5RQB4-GVJC76Z-GLPVVFO-LQW5MZ4-KQR3UKK-D4JPEAQ";
+
+    fn data_path(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/data")
+            .join(name)
+    }
+
+    #[test]
+    fn test_parse_canonical() {
+        let id: DeviceId = DEVICE_ID.parse().unwrap();
+        assert_eq!(id.to_string(), DEVICE_ID);
+    }
+
+    #[test]
+    fn test_parse_undashed_lowercase() {
+        let id: DeviceId = DEVICE_ID.replace('-', "").to_lowercase().parse().unwrap();
+        assert_eq!(id.to_string(), DEVICE_ID);
+    }
+
+    #[test]
+    fn test_parse_without_check_characters() {
+        let id: DeviceId = "MFZWI3DBONSGYYLTMRWGC43ENRQXGZDMMFZWI3DBONSGYYLTMRWA"
+            .parse()
+            .unwrap();
+        assert_eq!(id.to_string(), DEVICE_ID);
+    }
+
+    #[test]
+    fn test_parse_typos() {
+        // 0 and 1 are commonly typed instead of O and I
+        let id: DeviceId = "MFZWI3D-B0NSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-B0NSGYY-LTMRWAD"
+            .parse()
+            .unwrap();
+        assert_eq!(id.to_string(), DEVICE_ID);
+    }
+
+    #[test]
+    fn test_invalid_check_character() {
+        let result: Result<DeviceId> =
+            "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAE".parse();
+        assert!(matches!(result, Err(Error::InvalidDeviceIdError(_))));
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let result: Result<DeviceId> = "MFZWI3D-BONSGYC".parse();
+        assert!(matches!(result, Err(Error::InvalidDeviceIdError(_))));
+    }
+
+    #[test]
+    fn test_invalid_characters() {
+        let result: Result<DeviceId> =
+            "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRW!D".parse();
+        assert!(matches!(result, Err(Error::InvalidDeviceIdError(_))));
+    }
+
+    #[test]
+    fn test_serde_transparent() {
+        let id: DeviceId = DEVICE_ID.parse().unwrap();
+        let json = format!("\"{DEVICE_ID}\"");
+        let parsed: DeviceId = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, id);
+        assert_eq!(serde_json::to_string(&id).unwrap(), json);
+        assert!(serde_json::from_str::<DeviceId>("\"foo\"").is_err());
+    }
+
+    #[test]
+    fn test_empty() {
+        // The empty ID of the default device is modelled as `None` instead
+        for empty in ["", "-------", " "] {
+            assert!(matches!(
+                empty.parse::<DeviceId>(),
+                Err(Error::InvalidDeviceIdError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_from_pem_file() {
+        let id = DeviceId::from_certificate_file(data_path("cert.pem")).unwrap();
+        assert_eq!(id.to_string(), CERT_DEVICE_ID);
+    }
+
+    #[test]
+    fn test_from_der_file() {
+        let id = DeviceId::from_certificate_file(data_path("cert.der")).unwrap();
+        assert_eq!(id.to_string(), CERT_DEVICE_ID);
+    }
+
+    #[test]
+    fn test_from_pem_invalid() {
+        let result =
+            DeviceId::from_pem("-----BEGIN CERTIFICATE-----\n!!!\n-----END CERTIFICATE-----");
+        assert!(matches!(result, Err(Error::InvalidCertificateError(_))));
+
+        let result = DeviceId::from_pem("-----BEGIN CERTIFICATE-----\nAAAA");
+        assert!(matches!(result, Err(Error::InvalidCertificateError(_))));
+    }
+
+    #[test]
+    fn test_from_missing_file() {
+        let result = DeviceId::from_certificate_file(data_path("does-not-exist.pem"));
+        assert!(matches!(result, Err(Error::IoError(_))));
+    }
+}
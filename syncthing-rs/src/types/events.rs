@@ -1,13 +1,25 @@
 //! Event types, as defined [here](https://docs.syncthing.net/dev/events.html)
 use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser::SerializeStruct};
+use syncthing_macros::Kind;
 
-use super::config::{
-    Defaults, DeviceConfiguration, FolderConfiguration, GuiConfiguration, LDAPConfiguration,
-    NewDeviceConfiguration, ObservedDevice,
+use super::{
+    config::{Configuration, NewDeviceConfiguration},
+    db::{FolderState, RemoteState},
+    device_id::DeviceId,
 };
 
+// Moved to `db`, as it is also returned by `/rest/db/status`
+pub use super::db::FolderSummary;
+
+/// Former name of [`FolderState`], which is also used by `/rest/db/status`.
+///
+/// Unlike before, `"unknown"` is no longer a unit variant, but parsed as
+/// `FolderState::Unknown("unknown".to_string())` like any other unknown state.
+#[deprecated(note = "use `FolderState` instead")]
+pub type StateChangedState = FolderState;
+
 /// Represents an [Event](https://docs.syncthing.net/dev/events.html)
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -20,26 +32,26 @@ pub struct Event {
     pub ty: EventType,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-#[serde(tag = "type", content = "data")]
+/// The type and data of an [`Event`]. Its [`kind`](EventType::kind) is an
+/// [`EventTypeKind`], which can be used to only subscribe to certain events.
+///
+/// Event types which are not known to this crate, e.g. because they were
+/// added in a newer version of Syncthing, are parsed as
+/// [`Unknown`](EventType::Unknown). This also applies to `ClusterPendingChanged`,
+/// which is not part of the documented events and has no specified data.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Kind)]
+// The derived implementations are wrapped to handle unknown event types
+#[serde(remote = "Self", tag = "type", content = "data")]
 pub enum EventType {
     ClusterConfigReceived {
-        device: String,
-    },
-    #[serde(rename_all = "camelCase")]
-    ConfigSaved {
-        version: u64,
-        folders: Vec<FolderConfiguration>,
-        devices: Vec<DeviceConfiguration>,
-        gui: GuiConfiguration,
-        ldap: LDAPConfiguration,
-        remote_ignored_devices: Vec<ObservedDevice>,
-        defaults: Box<Defaults>,
+        device: DeviceId,
     },
+    /// The configuration was saved, contains the new configuration.
+    ConfigSaved(Box<Configuration>),
     #[serde(rename_all = "camelCase")]
     DeviceConnected {
         addr: String,
-        id: String,
+        id: DeviceId,
         device_name: String,
         client_name: String,
         client_version: String,
@@ -48,18 +60,24 @@ pub enum EventType {
     },
     DeviceDisconnected {
         error: String,
-        id: String,
+        id: DeviceId,
     },
     DeviceDiscovered {
         addrs: Vec<String>,
-        device: String,
+        device: DeviceId,
     },
     DevicePaused {
-        device: String,
+        device: DeviceId,
+    },
+    /// Deprecated, replaced by
+    /// [`PendingDevicesChanged`](EventType::PendingDevicesChanged).
+    DeviceRejected {
+        address: String,
+        device: DeviceId,
+        name: String,
     },
-    DeviceRejected {}, // Deprecated
     DeviceResumed {
-        device: String,
+        device: DeviceId,
     },
     DownloadProgress {
         #[serde(flatten)]
@@ -69,14 +87,14 @@ pub enum EventType {
     #[serde(rename_all = "camelCase")]
     FolderCompletion {
         completion: f64,
-        device: String,
+        device: DeviceId,
         folder: String,
         global_bytes: u64,
         global_items: u64,
         need_bytes: u64,
         need_deletes: u64,
         need_items: u64,
-        remote_state: String,
+        remote_state: RemoteState,
         sequence: u64,
     },
     FolderErrors {
@@ -87,7 +105,14 @@ pub enum EventType {
         id: String,
         label: String,
     },
-    FolderRejected {}, // Deprecated
+    /// Deprecated, replaced by
+    /// [`PendingFoldersChanged`](EventType::PendingFoldersChanged).
+    #[serde(rename_all = "camelCase")]
+    FolderRejected {
+        device: DeviceId,
+        folder: String,
+        folder_label: String,
+    },
     FolderResumed {
         id: String,
         label: String,
@@ -102,25 +127,29 @@ pub enum EventType {
         folder: String,
         summary: FolderSummary,
     },
+    /// The filesystem watcher of a folder failed or recovered. Both states
+    /// are error messages, `None` if the watcher was or is running.
     FolderWatchStateChanged {
         folder: String,
-        from: String,
-        to: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        from: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        to: Option<String>,
     },
     ItemFinished {
         item: String,
         folder: String,
         error: Option<String>,
         #[serde(rename = "type")]
-        ty: String,
-        action: String,
+        ty: ItemType,
+        action: ItemAction,
     },
     ItemStarted {
         item: String,
         folder: String,
         #[serde(rename = "type")]
-        ty: String,
-        action: String,
+        ty: ItemType,
+        action: ItemAction,
     },
     ListenAddressesChanged {
         address: ListenAddressChanged,
@@ -128,41 +157,50 @@ pub enum EventType {
         lan: Option<Vec<ListenAddressChanged>>,
     },
     LocalChangeDetected {
-        action: String,
+        action: ChangeAction,
         folder: String,
         #[serde(rename = "folderID")]
         folder_id: String,
         label: String,
         path: String,
         #[serde(rename = "type")]
-        ty: String,
+        ty: ItemType,
     },
     LocalIndexUpdated {
         folder: String,
         items: u64,
         filenames: Vec<String>,
         sequence: u64,
+        /// Deprecated, the same as `sequence`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<u64>,
     },
     #[serde(rename_all = "camelCase")]
     LoginAttempt {
         remote_address: String,
         username: String,
         success: bool,
+        /// The address of the proxy, if the request was forwarded by one.
+        #[serde(skip_serializing_if = "Option::is_none")]
         proxy: Option<String>,
     },
     PendingDevicesChanged {
+        #[serde(skip_serializing_if = "Option::is_none")]
         added: Option<Vec<AddedPendingDeviceChanged>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         removed: Option<Vec<RemovedPendingDeviceChanged>>,
     },
     PendingFoldersChanged {
+        #[serde(skip_serializing_if = "Option::is_none")]
         added: Option<Vec<AddedPendingFolderChanged>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         removed: Option<Vec<RemovedPendingFolderChanged>>,
     },
     #[serde(rename_all = "camelCase")]
     RemoteChangeDetected {
         #[serde(rename = "type")]
-        ty: String,
-        action: String,
+        ty: ItemType,
+        action: ChangeAction,
         folder: String,
         #[serde(rename = "folderID")]
         folder_id: String,
@@ -172,30 +210,82 @@ pub enum EventType {
     },
     RemoteDownloadProgress {
         state: HashMap<String, u64>,
-        device: String,
+        device: DeviceId,
         folder: String,
     },
     RemoteIndexUpdated {
-        device: String,
+        device: DeviceId,
         folder: String,
         items: u64,
+        sequence: u64,
+        /// Deprecated, the same as `sequence`.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<u64>,
     },
     Starting {
         home: String,
+        #[serde(rename = "myID")]
+        my_id: DeviceId,
     },
     StartupComplete {
         #[serde(rename = "myID")]
-        my_id: String,
+        my_id: DeviceId,
     },
     StateChanged {
         folder: String,
-        from: StateChangedState,
+        from: FolderState,
+        /// Seconds spent in the previous state.
+        #[serde(skip_serializing_if = "Option::is_none")]
         duration: Option<f64>,
-        to: StateChangedState,
+        to: FolderState,
+        /// The error message if the new state is [`FolderState::Error`].
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// An event of a type which is not known to this crate.
+    #[serde(skip)]
+    Unknown {
+        ty: String,
+        data: serde_json::Value,
     },
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+impl Serialize for EventType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Unknown { ty, data } => {
+                let mut state = serializer.serialize_struct("EventType", 2)?;
+                state.serialize_field("type", ty)?;
+                state.serialize_field("data", data)?;
+                state.end()
+            }
+            known => Self::serialize(known, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EventType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Tagged {
+            #[serde(rename = "type")]
+            ty: String,
+            #[serde(default)]
+            data: serde_json::Value,
+        }
+
+        let Tagged { ty, data } = Tagged::deserialize(deserializer)?;
+        let known = EventTypeKind::ALL
+            .iter()
+            .any(|kind| *kind != EventTypeKind::Unknown && kind.as_str() == ty);
+        if !known {
+            return Ok(Self::Unknown { ty, data });
+        }
+        Self::deserialize(serde_json::json!({"type": ty, "data": data})).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ConnectionType {
     #[serde(rename = "tcp-client")]
     TCPClient,
@@ -205,53 +295,55 @@ pub enum ConnectionType {
     RelayClient,
     #[serde(rename = "relay-server")]
     RelayServer,
+    #[serde(rename = "quic-client")]
+    QuicClient,
     #[serde(rename = "quic-server")]
     QuicServer,
+    /// Any other type, e.g. an empty string if the device is not connected.
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-pub struct FolderError {
-    pub error: String,
-    pub path: String,
+/// Type of a file, directory or symlink in item and change events.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemType {
+    File,
+    Dir,
+    Symlink,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// What happens to an item in [`ItemStarted`](EventType::ItemStarted)
+/// and [`ItemFinished`](EventType::ItemFinished) events.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemAction {
+    Update,
+    Delete,
+    /// Only the metadata, such as permissions, changes.
+    Metadata,
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+/// How an item was changed in [`LocalChangeDetected`](EventType::LocalChangeDetected)
+/// and [`RemoteChangeDetected`](EventType::RemoteChangeDetected) events.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeAction {
+    Added,
+    Deleted,
+    Modified,
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
-#[serde(rename_all = "camelCase")]
-pub struct FolderSummary {
-    pub errors: i64,
-    pub global_files: i64,
-    pub global_directories: i64,
-    pub global_symlinks: i64,
-    pub global_deleted: i64,
-    pub global_bytes: i64,
-    pub global_total_items: i64,
-    pub local_files: i64,
-    pub local_directories: i64,
-    pub local_symlinks: i64,
-    pub local_deleted: i64,
-    pub local_bytes: i64,
-    pub local_total_items: i64,
-    pub need_files: i64,
-    pub need_directories: i64,
-    pub need_symlinks: i64,
-    pub need_deletes: i64,
-    pub need_bytes: i64,
-    pub need_total_items: i64,
-    pub receive_only_changed_files: i64,
-    pub receive_only_changed_directories: i64,
-    pub receive_only_changed_symlinks: i64,
-    pub receive_only_changed_deletes: i64,
-    pub receive_only_changed_bytes: i64,
-    pub receive_only_total_items: i64,
-    pub in_sync_files: i64,
-    pub in_sync_bytes: i64,
-    pub state: String,
-    pub state_changed: chrono::DateTime<chrono::Utc>,
+pub struct FolderError {
     pub error: String,
-    pub sequence: i64,
-    pub remote_sequence: HashMap<String, i64>,
-    pub ignore_patterns: bool,
-    pub watch_error: String,
+    pub path: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -265,6 +357,12 @@ pub struct ListenAddressChanged {
     pub force_query: bool,
     pub host: String,
     pub opaque: String,
+    #[serde(default)]
+    pub raw_path: String,
+    #[serde(default)]
+    pub raw_fragment: String,
+    #[serde(default)]
+    pub omit_host: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -288,7 +386,7 @@ pub struct FileDownloadProgress {
 pub struct AddedPendingDeviceChanged {
     pub address: std::net::SocketAddr,
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
     pub name: String,
 }
 
@@ -298,17 +396,17 @@ pub struct AddedPendingDeviceChanged {
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct RemovedPendingDeviceChanged {
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
 }
 
 /// Information provided by the API if there is a new pending folder
 /// in a [`PendingFoldersChanged`](https://docs.syncthing.net/events/pendingfolderschanged.html)
 /// event.
-#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct AddedPendingFolderChanged {
     #[serde(rename = "deviceID")]
-    pub device_id: String,
+    pub device_id: DeviceId,
     #[serde(rename = "folderID")]
     pub folder_id: String,
     pub folder_label: String,
@@ -324,7 +422,7 @@ pub struct RemovedPendingFolderChanged {
     /// A removed entry without `device_id`, means that the folder is
     /// no longer pending on any device.
     #[serde(rename = "deviceID")]
-    pub device_id: Option<String>,
+    pub device_id: Option<DeviceId>,
     #[serde(rename = "folderID")]
     pub folder_id: String,
 }
@@ -335,38 +433,183 @@ impl From<AddedPendingDeviceChanged> for NewDeviceConfiguration {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-#[serde(rename_all = "kebab-case")]
-pub enum StateChangedState {
-    Idle,
-    Scanning,
-    ScanWaiting,
-    SyncWaiting,
-    SyncPreparing,
-    Syncing,
-    Cleaning,
-    CleanWaiting,
-    Error,
-    Unknown,
-}
-
 #[cfg(test)]
 mod tests {
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::{
+        collections::HashSet,
+        net::{IpAddr, Ipv4Addr},
+        path::Path,
+    };
+
+    use serde_json::Value;
 
     use super::*;
 
+    // Example device id from the docs
+    const DEVICE_ID: &str = "MFZWI3D-BONSGYC-YLTMRWG-C43ENR5-QXGZDMM-FZWI3DP-BONSGYY-LTMRWAD";
+
+    #[test]
+    fn test_kind() {
+        let ty = EventType::Starting {
+            home: "/var/syncthing".to_string(),
+            my_id: DEVICE_ID.parse().unwrap(),
+        };
+        assert_eq!(ty.kind(), EventTypeKind::Starting);
+        // Kinds are named like the serialized event type
+        for kind in [EventTypeKind::Starting, EventTypeKind::FolderCompletion] {
+            assert_eq!(kind.as_str(), format!("{kind:?}"),);
+        }
+        let json = serde_json::to_value(&ty).unwrap();
+        assert_eq!(json["type"], ty.kind().as_str());
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let event: EventType = serde_json::from_value(serde_json::json!({
+            "type": "ItemStarted",
+            "data": {"item": "a", "folder": "default", "type": "file", "action": "metadata"}
+        }))
+        .unwrap();
+        assert_eq!(
+            event,
+            EventType::ItemStarted {
+                item: "a".to_string(),
+                folder: "default".to_string(),
+                ty: ItemType::File,
+                action: ItemAction::Metadata,
+            }
+        );
+
+        let json = serde_json::json!({
+            "type": "LocalChangeDetected",
+            "data": {
+                "action": "renamed",
+                "folder": "default",
+                "folderID": "default",
+                "label": "Default Folder",
+                "path": "a",
+                "type": "socket"
+            }
+        });
+        let event: EventType = serde_json::from_value(json.clone()).unwrap();
+        let EventType::LocalChangeDetected { action, ty, .. } = &event else {
+            panic!("unexpected event {event:?}");
+        };
+        assert_eq!(action, &ChangeAction::Unknown("renamed".to_string()));
+        assert_eq!(ty, &ItemType::Unknown("socket".to_string()));
+        // Unknown values are serialized as they were received
+        assert_eq!(serde_json::to_value(&event).unwrap(), json);
+
+        let event: EventType = serde_json::from_value(serde_json::json!({
+            "type": "StateChanged",
+            "data": {"folder": "default", "from": "sync-preparing", "to": "unknown"}
+        }))
+        .unwrap();
+        assert!(matches!(
+            event,
+            EventType::StateChanged {
+                from: FolderState::SyncPreparing,
+                to: FolderState::Unknown(ref to),
+                ..
+            } if to == "unknown"
+        ));
+    }
+
+    #[test]
+    fn test_unknown_type() {
+        let json = serde_json::json!({
+            "id": 7,
+            "globalID": 42,
+            "time": "2025-05-07T17:05:44.514050967+02:00",
+            "type": "SomethingNew",
+            "data": {"folder": "default", "count": 3}
+        });
+        let event: Event = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(event.id, 7);
+        assert_eq!(
+            event.ty,
+            EventType::Unknown {
+                ty: "SomethingNew".to_string(),
+                data: serde_json::json!({"folder": "default", "count": 3}),
+            }
+        );
+        assert_eq!(event.ty.kind(), EventTypeKind::Unknown);
+
+        let mut serialized = serde_json::to_value(&event).unwrap();
+        // The time is normalized to UTC
+        serialized["time"] = json["time"].clone();
+        assert_eq!(serialized, json);
+
+        let undocumented: EventType = serde_json::from_value(serde_json::json!({
+            "type": "ClusterPendingChanged",
+            "data": {}
+        }))
+        .unwrap();
+        assert_eq!(undocumented.kind(), EventTypeKind::Unknown);
+    }
+
+    /// Known event types with invalid data are not parsed as unknown.
+    #[test]
+    fn test_invalid_data() {
+        let result = serde_json::from_value::<EventType>(serde_json::json!({
+            "type": "Starting",
+            "data": {"home": 42}
+        }));
+        assert!(result.is_err());
+    }
+
+    /// Parses samples of every event type, which follow the examples of the
+    /// events documentation. Events of a live instance are checked by
+    /// `container_test_event_samples`.
+    #[test]
+    fn test_samples() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/events.json");
+        let samples: Vec<Value> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+        let mut kinds = HashSet::new();
+        for sample in &samples {
+            let event: Event = serde_json::from_value(sample.clone())
+                .unwrap_or_else(|e| panic!("could not parse {}: {e}", sample["type"]));
+            assert_eq!(event.ty.kind().as_str(), sample["type"]);
+            assert!(kinds.insert(event.ty.kind()));
+            if let EventType::ConfigSaved(config) = &event.ty {
+                // Whole numbers are serialized as floats, e.g. `10.0`
+                let data: Configuration = serde_json::from_value(sample["data"].clone()).unwrap();
+                assert_eq!(**config, data);
+                continue;
+            }
+            // No field is missing or misspelt
+            let serialized = serde_json::to_value(&event).unwrap();
+            assert_eq!(serialized["data"], sample["data"], "{}", sample["type"]);
+        }
+
+        // The whole configuration is sent, as returned by `/rest/config`
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/config.json");
+        let config: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        let saved = samples.iter().find(|s| s["type"] == "ConfigSaved").unwrap();
+        assert_eq!(saved["data"], config);
+
+        for kind in EventTypeKind::ALL {
+            assert!(
+                kinds.contains(kind) || *kind == EventTypeKind::Unknown,
+                "no sample of {kind}"
+            );
+        }
+    }
+
     #[test]
     fn test_new_device() {
+        let device_id: DeviceId = DEVICE_ID.parse().unwrap();
         let added = AddedPendingDeviceChanged {
             address: std::net::SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8384),
-            device_id: "foo".to_string(),
+            device_id,
             name: "bar".to_string(),
         };
 
         let new: NewDeviceConfiguration = added.into();
 
-        assert_eq!(new.get_device_id(), "foo");
+        assert_eq!(new.get_device_id(), &device_id);
         assert_eq!(new.get_name(), &Some("bar".to_string()));
     }
 }
@@ -2,5 +2,6 @@
 pub mod cluster;
 pub mod config;
 pub mod db;
+pub mod device_id;
 pub mod events;
 pub mod system;
@@ -1,12 +1,15 @@
 //! All types required for the system endpoints
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap, fmt, str::FromStr};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
+use super::{device_id::DeviceId, events::ConnectionType};
+use crate::error::{Error, Result};
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Connections {
-    pub connections: HashMap<String, Connection>,
+    pub connections: HashMap<DeviceId, Connection>,
     pub total: TotalConnections,
 }
 
@@ -23,7 +26,17 @@ pub struct Connection {
     pub paused: bool,
     pub started_at: chrono::DateTime<Utc>,
     #[serde(rename = "type")]
-    pub ty: String,
+    pub ty: ConnectionType,
+}
+
+/// Information about available upgrades, as returned by `/rest/system/upgrade`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeInfo {
+    pub latest: String,
+    pub newer: bool,
+    pub major_newer: bool,
+    pub running: String,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -33,3 +46,388 @@ pub struct TotalConnections {
     pub in_bytes_total: i64,
     pub out_bytes_total: i64,
 }
+
+/// Status of a listener, as contained in [`SystemStatus`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerStatus {
+    pub error: Option<String>,
+    pub lan_addresses: Option<Vec<String>>,
+    pub wan_addresses: Option<Vec<String>>,
+}
+
+/// Status of a discovery method, as contained in [`SystemStatus`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DiscoveryStatus {
+    pub error: Option<String>,
+}
+
+/// Result of the last connection attempt to an address, as contained
+/// in [`SystemStatus`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DialStatus {
+    pub when: chrono::DateTime<Utc>,
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub ok: bool,
+}
+
+/// Information about the running instance, as returned by `/rest/system/status`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemStatus {
+    /// Bytes of allocated heap memory.
+    pub alloc: u64,
+    /// Maps every listen address to its status.
+    pub connection_service_status: HashMap<String, ListenerStatus>,
+    pub discovery_enabled: bool,
+    /// Only set if discovery is enabled.
+    #[serde(default)]
+    pub discovery_methods: u64,
+    /// Maps every discovery method to its status. Only set if discovery is enabled.
+    #[serde(default)]
+    pub discovery_status: HashMap<String, DiscoveryStatus>,
+    pub goroutines: u64,
+    /// Maps every address to the result of the last connection attempt.
+    #[serde(default)]
+    pub last_dial_status: HashMap<String, DialStatus>,
+    #[serde(rename = "myID")]
+    pub my_id: DeviceId,
+    pub path_separator: String,
+    pub start_time: chrono::DateTime<Utc>,
+    /// Bytes of memory obtained from the operating system.
+    pub sys: u64,
+    pub themes: Vec<String>,
+    /// The home directory of the user running Syncthing.
+    pub tilde: String,
+    /// Seconds since the start.
+    pub uptime: u64,
+    pub ur_version_max: u64,
+}
+
+/// Build information, as returned by `/rest/system/version`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemVersion {
+    pub arch: String,
+    pub codename: String,
+    pub is_beta: bool,
+    pub is_candidate: bool,
+    pub is_release: bool,
+    pub long_version: String,
+    pub os: String,
+    pub stamp: String,
+    pub tags: Option<Vec<String>>,
+    pub user: String,
+    pub version: String,
+}
+
+impl SystemVersion {
+    /// Parses [`version`](SystemVersion::version) into a comparable [`SyncthingVersion`].
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the version does not follow semantic versioning,
+    /// e.g. for builds from an unknown source.
+    pub fn parse(&self) -> Result<SyncthingVersion> {
+        self.version.parse()
+    }
+}
+
+/// A parsed Syncthing version such as `v1.27.0-rc.2`, ordered
+/// according to semantic versioning.
+///
+/// ```
+/// use syncthing_rs::types::system::SyncthingVersion;
+///
+/// let version: SyncthingVersion = "v1.27.0-rc.2".parse().unwrap();
+/// assert!(version < SyncthingVersion::new(1, 27, 0));
+/// assert!(version > SyncthingVersion::new(1, 26, 1));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SyncthingVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Pre-release identifiers, such as `rc.2` or `dev.12.g0123abc`.
+    pub pre: Option<String>,
+    /// Build metadata after a `+`.
+    pub build: Option<String>,
+}
+
+impl SyncthingVersion {
+    /// Creates a release version.
+    #[must_use]
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+            build: None,
+        }
+    }
+
+    /// Parses the version from a long version string, such as
+    /// [`SystemVersion::long_version`], e.g.
+    /// `syncthing v1.10.0 "Fermium Flea" (go1.15.1 darwin-amd64) ...`.
+    ///
+    /// # Errors
+    ///
+    /// This method fails if the string contains no valid version.
+    pub fn from_long_version(long_version: &str) -> Result<Self> {
+        long_version
+            .split_whitespace()
+            .find(|word| word.starts_with('v'))
+            .ok_or_else(|| Error::InvalidVersionError(long_version.to_string()))?
+            .parse()
+    }
+
+    /// Returns `true` for release candidates, betas and development builds.
+    #[must_use]
+    pub fn is_pre_release(&self) -> bool {
+        self.pre.is_some()
+    }
+
+    /// Returns `true` for builds which are not based on a tagged version.
+    #[must_use]
+    pub fn is_dev(&self) -> bool {
+        self.pre
+            .as_deref()
+            .is_some_and(|pre| pre.split('.').any(|id| id == "dev"))
+    }
+}
+
+/// Compares pre-release identifiers as defined by semantic versioning.
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a_ids = a.split('.');
+    let mut b_ids = b.split('.');
+    loop {
+        let ord = match (a_ids.next(), b_ids.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+impl Ord for SyncthingVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            })
+            // Build metadata has no precedence, but keeps the order consistent with `Eq`
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl PartialOrd for SyncthingVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for SyncthingVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidVersionError(s.to_string());
+        let version = s.trim();
+        let version = version.strip_prefix('v').unwrap_or(version);
+        let (version, build) = match version.split_once('+') {
+            Some((version, build)) => (version, Some(build.to_string())),
+            None => (version, None),
+        };
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre.to_string())),
+            None => (version, None),
+        };
+
+        let mut parts = core.split('.').map(|part| part.parse::<u64>());
+        let (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        if pre.as_deref() == Some("") || build.as_deref() == Some("") {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            major,
+            minor,
+            patch,
+            pre,
+            build,
+        })
+    }
+}
+
+impl fmt::Display for SyncthingVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{pre}")?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, "+{build}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Paths used by the running instance, as returned by `/rest/system/paths`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemPaths {
+    #[serde(rename = "baseDir-config")]
+    pub base_dir_config: String,
+    #[serde(rename = "baseDir-data")]
+    pub base_dir_data: String,
+    #[serde(rename = "baseDir-userHome")]
+    pub base_dir_user_home: String,
+    pub cert_file: String,
+    pub config: String,
+    pub database: String,
+    pub def_folder: String,
+    pub https_cert_file: String,
+    pub https_key_file: String,
+    pub key_file: String,
+    /// All paths which differ between Syncthing versions.
+    #[serde(flatten)]
+    pub other: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        let version: SyncthingVersion = "v1.27.0-rc.2+5-g36c93b7".parse().unwrap();
+
+        assert_eq!(
+            version,
+            SyncthingVersion {
+                major: 1,
+                minor: 27,
+                patch: 0,
+                pre: Some("rc.2".to_string()),
+                build: Some("5-g36c93b7".to_string()),
+            }
+        );
+        assert_eq!(version.to_string(), "v1.27.0-rc.2+5-g36c93b7");
+        assert!(version.is_pre_release());
+        assert!(!version.is_dev());
+
+        let dev: SyncthingVersion = "v1.23.0-dev.12.g0123abc".parse().unwrap();
+        assert!(dev.is_dev());
+
+        assert!("unknown-dev".parse::<SyncthingVersion>().is_err());
+        assert!("v1.2".parse::<SyncthingVersion>().is_err());
+        assert!("v1.2.3.4".parse::<SyncthingVersion>().is_err());
+    }
+
+    #[test]
+    fn test_long_version() {
+        let version = SyncthingVersion::from_long_version(
+            r#"syncthing v1.10.0 "Fermium Flea" (go1.15.1 darwin-amd64) jb@kvin.kastelo.net 2020-09-15 20:05:13 UTC [stnoupgrade]"#,
+        )
+        .unwrap();
+
+        assert_eq!(version, SyncthingVersion::new(1, 10, 0));
+    }
+
+    #[test]
+    fn test_version_order() {
+        let parse = |s: &str| s.parse::<SyncthingVersion>().unwrap();
+
+        assert!(parse("v1.10.0") > parse("v1.9.9"));
+        assert!(parse("v2.0.0") > parse("v1.30.0"));
+        assert!(parse("v1.10.0") > parse("v1.10.0-rc.1"));
+        assert!(parse("v1.10.0-rc.10") > parse("v1.10.0-rc.2"));
+        assert!(parse("v1.10.0-rc.1") > parse("v1.10.0-beta.3"));
+        assert!(parse("v1.10.0-rc.1.1") > parse("v1.10.0-rc.1"));
+    }
+
+    #[test]
+    fn test_system_status() {
+        let json = r#"
+{
+  "alloc": 30618136,
+  "connectionServiceStatus": {
+    "dynamic+https://relays.syncthing.net/endpoint": {
+      "error": null,
+      "lanAddresses": [
+        "relay://23.92.71.120:443/?id=53STGR7-YBM6FCX-PAZ2RHM-YPY6OEJ-WYHVZO7-PCKQRCK-PZLTP7T-434XCAD&pingInterval=1m0s&networkTimeout=2m0s&sessionLimitBps=0&globalLimitBps=0&statusAddr=:22070&providedBy=ams-relay"
+      ],
+      "wanAddresses": [
+        "relay://23.92.71.120:443/?id=53STGR7-YBM6FCX-PAZ2RHM-YPY6OEJ-WYHVZO7-PCKQRCK-PZLTP7T-434XCAD&pingInterval=1m0s&networkTimeout=2m0s&sessionLimitBps=0&globalLimitBps=0&statusAddr=:22070&providedBy=ams-relay"
+      ]
+    },
+    "tcp://0.0.0.0:22000": {
+      "error": null,
+      "lanAddresses": ["tcp://0.0.0.0:22000"],
+      "wanAddresses": ["tcp://0.0.0.0:22000"]
+    },
+    "quic://0.0.0.0:22000": {
+      "error": "listen udp 0.0.0.0:22000: bind: address already in use",
+      "lanAddresses": null,
+      "wanAddresses": null
+    }
+  },
+  "cpuPercent": 0,
+  "discoveryEnabled": true,
+  "discoveryErrors": {
+    "global@https://discovery-v4-1.syncthing.net/v2/": "500 Internal Server Error"
+  },
+  "discoveryStatus": {
+    "IPv4 local": { "error": null },
+    "global@https://discovery-v4-1.syncthing.net/v2/": { "error": "500 Internal Server Error" }
+  },
+  "discoveryMethods": 8,
+  "goroutines": 49,
+  "lastDialStatus": {
+    "tcp://10.20.30.40": {
+      "when": "2022-02-14T16:35:56Z",
+      "error": "dial tcp 10.20.30.40:22000: i/o timeout"
+    },
+    "tcp://172.16.33.3:22000": { "when": "2022-02-14T16:35:56Z", "ok": true }
+  },
+  "myID": "P56IOI7-MZJNU2Y-IQGDREY-DM2MGTI-MGL3BXN-PQ6W5BM-TBBZ4TJ-XZWICQ2",
+  "pathSeparator": "/",
+  "startTime": "2016-06-06T19:41:43.039284753+02:00",
+  "sys": 42092792,
+  "themes": ["default", "dark"],
+  "tilde": "/Users/jb",
+  "uptime": 2635,
+  "urVersionMax": 3
+}
+"#;
+        let status: SystemStatus = serde_json::from_str(json).unwrap();
+
+        assert_eq!(status.connection_service_status.len(), 3);
+        assert_eq!(
+            status.connection_service_status["quic://0.0.0.0:22000"].lan_addresses,
+            None
+        );
+        assert!(status.last_dial_status["tcp://172.16.33.3:22000"].ok);
+        assert!(!status.last_dial_status["tcp://10.20.30.40"].ok);
+        assert_eq!(status.uptime, 2635);
+    }
+}